@@ -25,6 +25,14 @@ pub struct Options {
     /// Use default values for confirmation dialogues
     #[structopt(short = "y", long, global = true)]
     noconfirm: bool,
+
+    /// Confirm every action of performed rules before it runs
+    #[structopt(long, global = true)]
+    step: bool,
+
+    /// Report changes instead of making them
+    #[structopt(short = "n", long, global = true)]
+    dry_run: bool,
 }
 
 impl Options {
@@ -38,6 +46,16 @@ impl Options {
         self.noconfirm
     }
 
+    /// Getter for `step` option
+    pub fn step(&self) -> bool {
+        self.step
+    }
+
+    /// Getter for `dry_run` option
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
     /// Getter for linux distro identifier
     ///
     /// If linux distro isn't specified by the user, it's determined using
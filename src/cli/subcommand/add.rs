@@ -2,6 +2,7 @@
 //! symlink instead
 
 use crate::cli;
+use crate::config::Config;
 use crate::os::{get_file_name, move_file, symlink};
 use crate::types::UserPath;
 use std::error::Error;
@@ -19,6 +20,11 @@ pub struct Add {
     /// Store the file with a given name in the dotfiles directory
     #[structopt(long = "with_name", short = "o")]
     with_name: Option<OsString>,
+
+    /// Encrypt the file into the dotfiles directory instead of moving it. The
+    /// tool and recipients are taken from `conf.encryption`
+    #[structopt(long)]
+    encrypt: bool,
 }
 
 impl Add {
@@ -30,18 +36,52 @@ impl Add {
             None => get_file_name(&file)?,
         };
 
-        let dest = cli::options().dotfiles_dir().join(&with_name);
+        let mut dest = cli::options().dotfiles_dir().join(&with_name);
+        let config = match self.encrypt {
+            true => Some(Config::init()?),
+            false => None,
+        };
+        if let Some(config) = &config {
+            dest = config.encrypted_path(&dest);
+        }
 
+        let dry_run = cli::options().dry_run();
         if dest.exists() {
             print_warn!("File `{}` already exists", dest.display());
             if !confirm!("Replace it?"; true) {
                 print_info!("Aborting...");
                 return Ok(());
             }
-            print_info!("Replacing `{}` with a newly added file", dest.display());
-        } else {
+            if !dry_run {
+                print_info!("Replacing `{}` with a newly added file", dest.display());
+            }
+        } else if config.is_none() && !dry_run {
             print_info!("Moving `{}` to your dotfiles", file.display());
         }
+
+        if let Some(config) = &config {
+            if dry_run {
+                print_info!("Would encrypt `{}` to `{}`", file.display(), dest.display());
+                return Ok(());
+            }
+            print_info!("Encrypting `{}` to `{}`", file.display(), dest.display());
+            config.encrypt(file, &dest)?;
+            print_info!(
+                "Add `decrypt: {{{}: {}}}` action to a rule to install it",
+                config.encrypted_path(Path::new(with_name)).display(),
+                file.display()
+            );
+            return Ok(());
+        }
+
+        if dry_run {
+            print_info!(
+                "Would move `{}` to `{}` and link it back",
+                file.display(),
+                dest.display()
+            );
+            return Ok(());
+        }
         move_file(&file, &dest)?;
 
         let dest = dest.canonicalize()?;
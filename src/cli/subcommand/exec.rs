@@ -1,25 +1,183 @@
-//! Subcommand that performs specified action of a rule
+//! Subcommand that performs specified actions of given rules
 
-use crate::config::Config;
+use crate::config::{ActionsSelector, Config};
 use crate::types::Identifier;
 use std::error::Error;
+use std::str::FromStr;
 use structopt::StructOpt;
 
-/// Perform nth action of a given rule
+/// Perform specified actions of given rules
 #[derive(Debug, StructOpt)]
 pub struct Exec {
-    /// A given rule
-    #[structopt(required = true)]
+    /// Rules to perform, each optionally followed by `:` and an action
+    /// selector (e.g. `foo:install-plugins`) or by an index (counting from 1)
+    /// or an inclusive range of indices as a separate argument (e.g. `foo 2..4`)
+    #[structopt(required = true, value_name = "TARGETS")]
+    args: Vec<String>,
+
+    /// Perform dependencies of given rules before their actions
+    #[structopt(long)]
+    with_deps: bool,
+
+    /// List numbered and labelled actions of given rules instead of
+    /// performing them
+    #[structopt(short, long)]
+    list: bool,
+}
+
+/// A rule optionally followed by an actions selector, i.e. `rule[:selector]`
+#[derive(Debug)]
+struct ExecTarget {
     rule: Identifier,
+    selector: Option<ActionsSelector>,
+}
+
+impl FromStr for ExecTarget {
+    type Err = String;
 
-    /// Index of an action to perform (counting from 1)
-    #[structopt(required = true)]
-    n: usize,
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rule, selector) = match s.find(':') {
+            Some(pos) if pos + 1 == s.len() => {
+                return Err(format!("Missing actions selector after `{}`", s));
+            }
+            Some(pos) => (&s[..pos], Some(s[pos + 1..].parse()?)),
+            None => (s, None),
+        };
+        Ok(ExecTarget {
+            rule: rule.parse()?,
+            selector,
+        })
+    }
 }
 
 impl Exec {
+    /// Group arguments into targets. Indices and ranges given as separate
+    /// arguments are applied to the preceding rule
+    fn targets(&self) -> Result<Vec<ExecTarget>, String> {
+        let mut targets = Vec::<ExecTarget>::new();
+        for arg in &self.args {
+            let selector = match arg.parse() {
+                Ok(selector @ ActionsSelector::Index(_))
+                | Ok(selector @ ActionsSelector::Range(_, _)) => selector,
+                _ => {
+                    targets.push(arg.parse()?);
+                    continue;
+                }
+            };
+            match targets.last_mut() {
+                Some(target) if target.selector.is_none() => target.selector = Some(selector),
+                _ => Err(format!("Unexpected selector `{}`", arg))?,
+            }
+        }
+        Ok(targets)
+    }
+
     pub fn perform(&self) -> Result<(), Box<dyn Error>> {
         let config = Config::init()?;
-        Ok(config.try_get_rule(&self.rule)?.perform_nth(self.n)?)
+        let targets = self.targets()?;
+        for target in &targets {
+            config.try_get_rule(&target.rule)?;
+        }
+
+        if self.list {
+            for target in &targets {
+                println!("{}:", target.rule);
+                for action in config.try_get_rule(&target.rule)?.list_actions() {
+                    println!("  {}", action);
+                }
+            }
+            return Ok(());
+        }
+
+        if !self.with_deps {
+            for target in &targets {
+                Self::perform_target(&config, target)?;
+            }
+            return Ok(());
+        }
+
+        // Targets are performed in place of their rules in the dependency
+        // order, so that targets depending on each other are performed after
+        // their dependencies
+        let roots: Vec<_> = targets.iter().map(|target| &target.rule).collect();
+        let graph = config.get_deps_graph()?;
+        for ident in graph.resolve(roots)? {
+            let mut rule_targets = targets.iter().filter(|target| &target.rule == ident).peekable();
+            if rule_targets.peek().is_none() {
+                print_info!("Performing `{}`...", ident);
+                config.try_get_rule(ident)?.perform()?;
+            }
+            for target in rule_targets {
+                Self::perform_target(&config, target)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Perform actions of a target's rule chosen by its selector or all of
+    /// them if there is no selector
+    fn perform_target(config: &Config, target: &ExecTarget) -> Result<(), Box<dyn Error>> {
+        let all_actions = ActionsSelector::Range(None, None);
+        let selector = target.selector.as_ref().unwrap_or(&all_actions);
+        config.try_get_rule(&target.rule)?.perform_selected(selector)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ActionsSelector, Exec};
+
+    fn targets(args: &[&str]) -> Result<Vec<(String, Option<ActionsSelector>)>, String> {
+        let exec = Exec {
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            with_deps: false,
+            list: false,
+        };
+        let targets = exec.targets()?;
+        Ok(targets
+            .into_iter()
+            .map(|target| (target.rule.to_string(), target.selector))
+            .collect())
+    }
+
+    #[test]
+    fn rules_without_selectors() {
+        assert_eq!(
+            targets(&["a", "b"]),
+            Ok(vec![("a".to_string(), None), ("b".to_string(), None)])
+        );
+    }
+
+    #[test]
+    fn selectors_after_colon() {
+        let label = ActionsSelector::Label("plugins".parse().unwrap());
+        assert_eq!(
+            targets(&["a:plugins", "b:2..3"]),
+            Ok(vec![
+                ("a".to_string(), Some(label)),
+                ("b".to_string(), Some(ActionsSelector::Range(Some(2), Some(3)))),
+            ])
+        );
+    }
+
+    #[test]
+    fn separate_selectors_apply_to_preceding_rule() {
+        assert_eq!(
+            targets(&["a", "2", "b", "..4", "c"]),
+            Ok(vec![
+                ("a".to_string(), Some(ActionsSelector::Index(2))),
+                ("b".to_string(), Some(ActionsSelector::Range(None, Some(4)))),
+                ("c".to_string(), None),
+            ])
+        );
+    }
+
+    #[test]
+    fn unexpected_selectors() {
+        assert!(targets(&["2", "a"]).is_err());
+        assert!(targets(&["a", "2", "3"]).is_err());
+        assert!(targets(&["a:1", "2"]).is_err());
+        assert!(targets(&["a:"]).is_err());
     }
 }
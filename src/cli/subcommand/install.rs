@@ -1,6 +1,8 @@
 //! Subcommand that fully performes given rules
 
-use crate::config::Config;
+use crate::cli;
+use crate::config::{CollectedNotes, Config, Lockfile, PkgsBatch};
+use crate::state::State;
 use crate::types::Identifier;
 use std::error::Error;
 use structopt::StructOpt;
@@ -11,6 +13,16 @@ pub struct Install {
     /// Rules to be installed
     #[structopt(required = true)]
     rules: Vec<Identifier>,
+
+    /// Install packages of all the performed rules at once for every package
+    /// manager
+    #[structopt(long)]
+    batch_pkgs: bool,
+
+    /// Warn about installed packages, which versions differ from the ones
+    /// recorded in the lockfile
+    #[structopt(long)]
+    locked: bool,
 }
 
 impl Install {
@@ -22,10 +34,42 @@ impl Install {
 
         let graph = config.get_deps_graph()?;
         let resolved = graph.resolve(self.rules.iter().collect())?;
-        for ident in resolved {
+        let lockfile = match self.locked {
+            true => Some(Lockfile::load()?),
+            false => None,
+        };
+        let mut batch = None;
+        if self.batch_pkgs {
+            batch = Some(PkgsBatch::from(config.pkgs_of(resolved.iter().copied())?));
+        }
+
+        let notes = CollectedNotes::default();
+        for &ident in &resolved {
             print_info!("Performing `{}`...", ident);
-            config.try_get_rule(&ident)?.perform()?;
+            config
+                .try_get_rule(ident)?
+                .with_pkgs_batch(batch.as_ref())
+                .with_notes(Some(&notes))
+                .perform()?;
+            if !cli::options().dry_run() {
+                State::update(|state| state.add_installed(ident.clone()))?;
+            }
+        }
+
+        if let Some(lockfile) = lockfile {
+            let pkgs = config.resolve_pkg_names(&config.pkgs_of(resolved.iter().copied())?)?;
+            let installed = config.pkg_versions(&pkgs)?;
+            for mismatch in lockfile.mismatches(&pkgs, &installed) {
+                print_warn!(
+                    "`{}` ({}): installed {}, locked {}",
+                    mismatch.pkg,
+                    mismatch.mngr,
+                    mismatch.installed.unwrap_or("nothing"),
+                    mismatch.locked
+                );
+            }
         }
+        notes.print();
         Ok(())
     }
 }
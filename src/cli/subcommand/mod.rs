@@ -4,6 +4,8 @@ mod add;
 mod completion;
 mod exec;
 mod install;
+mod pkgs;
+mod uninstall;
 
 use std::error::Error;
 use structopt::StructOpt;
@@ -12,11 +14,15 @@ use add::Add;
 use completion::Completion;
 use exec::Exec;
 use install::Install;
+use pkgs::Pkgs;
+use uninstall::Uninstall;
 
 #[derive(Debug, StructOpt)]
 pub enum Subcommand {
     Install(Install),
+    Uninstall(Uninstall),
     Exec(Exec),
+    Pkgs(Pkgs),
     Completion(Completion),
     Add(Add),
 }
@@ -26,7 +32,9 @@ impl Subcommand {
     pub fn perform(&self) -> Result<(), Box<dyn Error>> {
         match self {
             Subcommand::Install(sub) => sub.perform(),
+            Subcommand::Uninstall(sub) => sub.perform(),
             Subcommand::Exec(sub) => sub.perform(),
+            Subcommand::Pkgs(sub) => sub.perform(),
             Subcommand::Completion(sub) => sub.perform(),
             Subcommand::Add(sub) => sub.perform(),
         }
@@ -0,0 +1,162 @@
+//! Subcommand that manages packages of rules
+
+use crate::cli;
+use crate::config::{Config, Lockfile, PkgsByManager};
+use crate::state::State;
+use crate::types::Identifier;
+use std::error::Error;
+use std::str::FromStr;
+use structopt::StructOpt;
+
+/// Manage packages installed by rules
+#[derive(Debug, StructOpt)]
+pub struct Pkgs {
+    #[structopt(subcommand)]
+    cmd: PkgsCmd,
+}
+
+#[derive(Debug, StructOpt)]
+enum PkgsCmd {
+    /// Remove packages of given rules, that aren't used by other installed
+    /// rules
+    Remove {
+        #[structopt(required = true)]
+        rules: Vec<Identifier>,
+    },
+
+    /// Upgrade packages of package managers used by given rules or by all the
+    /// installed rules
+    Upgrade { rules: Vec<Identifier> },
+
+    /// List packages of given rules and their dependencies grouped by package
+    /// managers. Packages of all the rules are listed by default
+    List {
+        rules: Vec<Identifier>,
+
+        /// Output format: `plain`, `json` or `cmds` (one install command per
+        /// package manager)
+        #[structopt(long, default_value = "plain", possible_values = &["plain", "json", "cmds"])]
+        format: ListFormat,
+    },
+
+    /// Record installed versions of packages of given rules and their
+    /// dependencies into the lockfile. The whole lockfile is rewritten with
+    /// versions of packages of all the rules by default
+    Lock { rules: Vec<Identifier> },
+}
+
+/// Output format of `dotmake pkgs list`
+#[derive(Debug, Copy, Clone)]
+enum ListFormat {
+    Plain,
+    Json,
+    Cmds,
+}
+
+impl FromStr for ListFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(ListFormat::Plain),
+            "json" => Ok(ListFormat::Json),
+            "cmds" => Ok(ListFormat::Cmds),
+            _ => Err(format!("invalid format: {}", s)),
+        }
+    }
+}
+
+impl Pkgs {
+    pub fn perform(&self) -> Result<(), Box<dyn Error>> {
+        let config = Config::init()?;
+        let mut state = State::load()?;
+        match &self.cmd {
+            PkgsCmd::Remove { rules } => {
+                let mut pkgs = config.pkgs_of(rules)?;
+                let other_rules = state
+                    .installed_rules()
+                    .filter(|rule| !rules.contains(rule) && config.get_rule(rule).is_some());
+                for (mngr, used) in config.pkgs_of(other_rules)? {
+                    if let Some(pkgs) = pkgs.get_mut(&mngr) {
+                        pkgs.retain(|pkg| !used.contains(pkg));
+                    }
+                }
+                config.remove_pkgs(&pkgs)?;
+
+                if !cli::options().dry_run() {
+                    for rule in rules {
+                        state.remove_installed(rule);
+                    }
+                    state.save()?;
+                }
+            }
+            PkgsCmd::Upgrade { rules } => {
+                let pkgs = match rules.is_empty() {
+                    false => config.pkgs_of(rules)?,
+                    true => config.pkgs_of(
+                        state
+                            .installed_rules()
+                            .filter(|rule| config.get_rule(rule).is_some()),
+                    )?,
+                };
+                config.upgrade_pkg_managers(pkgs.keys())?;
+            }
+            PkgsCmd::List { rules, format } => {
+                let roots = match rules.is_empty() {
+                    false => rules.iter().collect(),
+                    true => config.rules().collect(),
+                };
+                let graph = config.get_deps_graph()?;
+                let pkgs = config.pkgs_of(graph.resolve(roots)?)?;
+                Self::list(&config, &config.resolve_pkg_names(&pkgs)?, *format)?;
+            }
+            PkgsCmd::Lock { rules } => {
+                let (roots, mut lockfile) = match rules.is_empty() {
+                    false => (rules.iter().collect(), Lockfile::load_or_default()?),
+                    true => (config.rules().collect(), Lockfile::default()),
+                };
+                let graph = config.get_deps_graph()?;
+                let pkgs = config.pkgs_of(graph.resolve(roots)?)?;
+                let versions = config.pkg_versions(&config.resolve_pkg_names(&pkgs)?)?;
+                if cli::options().dry_run() {
+                    for (mngr, versions) in &versions {
+                        for (pkg, version) in versions {
+                            print_info!("Would record `{}` {} of `{}`", pkg, version, mngr);
+                        }
+                    }
+                } else {
+                    lockfile.update(versions);
+                    lockfile.save()?;
+                    print_info!("Versions are recorded in `{}`", Lockfile::path()?.display());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Print given packages in a given format
+    fn list(
+        config: &Config,
+        pkgs: &PkgsByManager,
+        format: ListFormat,
+    ) -> Result<(), Box<dyn Error>> {
+        match format {
+            ListFormat::Plain => {
+                for (mngr, pkgs) in pkgs {
+                    println!("{}:", mngr);
+                    for pkg in pkgs {
+                        println!("    {}", pkg);
+                    }
+                }
+            }
+            ListFormat::Json => println!("{}", serde_json::to_string_pretty(pkgs)?),
+            ListFormat::Cmds => {
+                for (mngr, pkgs) in pkgs {
+                    let pkgs: Vec<_> = pkgs.iter().cloned().collect();
+                    println!("{}", config.pkgs_install_cmd(mngr, &pkgs)?);
+                }
+            }
+        }
+        Ok(())
+    }
+}
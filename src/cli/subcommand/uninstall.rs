@@ -0,0 +1,34 @@
+//! Subcommand that undoes given rules
+
+use crate::cli;
+use crate::config::Config;
+use crate::state::State;
+use crate::types::Identifier;
+use std::error::Error;
+use structopt::StructOpt;
+
+/// Undo actions of given rules, that support it
+#[derive(Debug, StructOpt)]
+pub struct Uninstall {
+    /// Rules to be uninstalled
+    #[structopt(required = true)]
+    rules: Vec<Identifier>,
+}
+
+impl Uninstall {
+    pub fn perform(&self) -> Result<(), Box<dyn Error>> {
+        let config = Config::init()?;
+        for rule in &self.rules {
+            config.try_get_rule(rule)?;
+        }
+
+        for ident in self.rules.iter().rev() {
+            print_info!("Uninstalling `{}`...", ident);
+            config.try_get_rule(ident)?.uninstall()?;
+            if !cli::options().dry_run() {
+                State::update(|state| state.remove_installed(ident))?;
+            }
+        }
+        Ok(())
+    }
+}
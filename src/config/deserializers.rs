@@ -1,40 +1,83 @@
 //! General structures providing custom `Deserialize` implementation
+//!
+//! Values that can be given in several forms are told apart by their type
+//! (e.g. a sequence or a map) instead of using untagged enums, so that errors
+//! of the chosen form are reported as they are
 
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{self, Deserialize, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use std::fmt;
+use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 
-/// A helper for deserializing `List` as either a sequence or a singletone
-#[derive(Debug, Deserialize)]
-#[serde(untagged)]
-enum ListEnum<T> {
-    Sequence(Vec<T>),
-    Singletone(T),
-}
+/// Implements `Visitor` methods for scalar values, that pass a deserializer of
+/// the value to a given method of the visitor
+macro_rules! visit_scalars {
+    ($forward: ident) => {
+        visit_scalars!(
+            $forward;
+            visit_bool: bool,
+            visit_i64: i64,
+            visit_u64: u64,
+            visit_f64: f64,
+            visit_str: &str,
+            visit_string: String
+        );
 
-impl<T> ListEnum<T> {
-    fn to_vec(self) -> Vec<T> {
-        match self {
-            ListEnum::Sequence(vec) => vec,
-            ListEnum::Singletone(elem) => vec![elem],
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            self.$forward(().into_deserializer())
         }
-    }
+    };
+    ($forward: ident; $($method: ident: $ty: ty),*) => {
+        $(fn $method<E: de::Error>(self, v: $ty) -> Result<Self::Value, E> {
+            self.$forward(v.into_deserializer())
+        })*
+    };
 }
 
 /// Deserialize a vector, but instead of only accepting a sequence of items, it
 /// also accepts a single value, witch is treated as a singleton
-#[derive(Debug, Deserialize)]
-#[serde(from = "ListEnum<T>")]
+#[derive(Debug)]
 pub struct List<T> {
     elems: Vec<T>,
 }
 
-impl<T> From<ListEnum<T>> for List<T> {
-    fn from(list_enum: ListEnum<T>) -> Self {
-        List {
-            elems: list_enum.to_vec(),
-        }
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for List<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ListVisitor(PhantomData))
     }
 }
 
+struct ListVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> ListVisitor<T> {
+    /// Deserialize a singleton
+    fn single<D: Deserializer<'de>>(self, deserializer: D) -> Result<List<T>, D::Error> {
+        Ok(vec![T::deserialize(deserializer)?].into())
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for ListVisitor<T> {
+    type Value = List<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a value or a sequence of values")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        Ok(Vec::deserialize(SeqAccessDeserializer::new(seq))?.into())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        self.single(MapAccessDeserializer::new(map))
+    }
+
+    visit_scalars!(single);
+}
+
 impl<T> From<Vec<T>> for List<T> {
     fn from(elems: Vec<T>) -> Self {
         List { elems }
@@ -66,3 +109,58 @@ impl<T> Default for List<T> {
         List { elems: vec![] }
     }
 }
+
+/// A value, that is given either in a short form or in a full one. Maps are
+/// deserialized as the full form and everything else as the short one
+#[derive(Debug)]
+pub enum ShortOrFull<S, F> {
+    Short(S),
+    Full(F),
+}
+
+impl<'de, S, F> Deserialize<'de> for ShortOrFull<S, F>
+where
+    S: Deserialize<'de>,
+    F: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ShortOrFullVisitor(PhantomData))
+    }
+}
+
+struct ShortOrFullVisitor<S, F>(PhantomData<(S, F)>);
+
+impl<'de, S: Deserialize<'de>, F> ShortOrFullVisitor<S, F> {
+    /// Deserialize the short form
+    fn short<D>(self, deserializer: D) -> Result<ShortOrFull<S, F>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(ShortOrFull::Short(S::deserialize(deserializer)?))
+    }
+}
+
+impl<'de, S, F> Visitor<'de> for ShortOrFullVisitor<S, F>
+where
+    S: Deserialize<'de>,
+    F: Deserialize<'de>,
+{
+    type Value = ShortOrFull<S, F>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a value or a map")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        self.short(SeqAccessDeserializer::new(seq))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        Ok(ShortOrFull::Full(F::deserialize(MapAccessDeserializer::new(map))?))
+    }
+
+    visit_scalars!(short);
+}
@@ -0,0 +1,161 @@
+//! Lockfile, that records versions of installed packages. It's placed beside
+//! the configuration file and has the same base name, e.g. `dotm-arch.lock`
+
+use super::{Config, ConfigError, PkgVersions, PkgsByManager};
+use crate::os;
+use crate::types::Identifier;
+use std::path::PathBuf;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Lockfile {
+    versions: PkgVersions,
+}
+
+/// A package, which installed version differs from the locked one
+#[derive(Debug)]
+pub struct VersionMismatch<'a> {
+    pub mngr: &'a Identifier,
+    pub pkg: &'a str,
+    pub locked: &'a str,
+    pub installed: Option<&'a str>,
+}
+
+impl Lockfile {
+    /// Path of the lockfile
+    pub fn path() -> Result<PathBuf, ConfigError> {
+        Ok(Config::base_path()?.with_extension("lock"))
+    }
+
+    /// Load the lockfile
+    ///
+    /// # Errors
+    /// Returns an error if the lockfile doesn't exist or is invalid
+    pub fn load() -> Result<Self, ConfigError> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Err(ConfigError::LockfileNotFound(path));
+        }
+        serde_json::from_str(&os::read_to_string(&path)?)
+            .map_err(|err| ConfigError::InvalidLockfile { path, err })
+    }
+
+    /// Load the lockfile if it exists. Otherwise an empty lockfile is returned
+    pub fn load_or_default() -> Result<Self, ConfigError> {
+        match Self::load() {
+            Err(ConfigError::LockfileNotFound(_)) => Ok(Lockfile::default()),
+            lockfile => lockfile,
+        }
+    }
+
+    /// Save the lockfile
+    pub fn save(&self) -> Result<(), ConfigError> {
+        let json = serde_json::to_string_pretty(self).unwrap();
+        Ok(os::write_file(Self::path()?, json + "\n")?)
+    }
+
+    /// Record given versions, replacing the locked ones
+    pub fn update(&mut self, versions: PkgVersions) {
+        for (mngr, versions) in versions {
+            self.versions.entry(mngr).or_default().extend(versions);
+        }
+    }
+
+    /// Compare installed versions of given packages with the locked ones.
+    /// Packages that aren't locked and packages of managers, which weren't
+    /// queried for versions, are ignored
+    pub fn mismatches<'a>(
+        &'a self,
+        pkgs: &'a PkgsByManager,
+        installed: &'a PkgVersions,
+    ) -> Vec<VersionMismatch<'a>> {
+        let mut mismatches = Vec::new();
+        for (mngr, pkgs) in pkgs {
+            let (locked, installed) = match (self.versions.get(mngr), installed.get(mngr)) {
+                (Some(locked), Some(installed)) => (locked, installed),
+                _ => continue,
+            };
+            for pkg in pkgs {
+                let locked = match locked.get(pkg) {
+                    Some(locked) => locked,
+                    None => continue,
+                };
+                let installed = installed.get(pkg);
+                if installed != Some(locked) {
+                    mismatches.push(VersionMismatch {
+                        mngr,
+                        pkg,
+                        locked,
+                        installed: installed.map(String::as_str),
+                    });
+                }
+            }
+        }
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lockfile, PkgVersions};
+    use crate::types::Identifier;
+    use maplit::{btreemap, btreeset};
+
+    fn ident(s: &str) -> Identifier {
+        s.parse().unwrap()
+    }
+
+    fn versions(mngr: &str, versions: &[(&str, &str)]) -> PkgVersions {
+        let versions = versions
+            .iter()
+            .map(|&(pkg, version)| (pkg.to_string(), version.to_string()))
+            .collect();
+        btreemap! { ident(mngr) => versions }
+    }
+
+    fn lockfile(mngr: &str, locked: &[(&str, &str)]) -> Lockfile {
+        let mut lockfile = Lockfile::default();
+        lockfile.update(versions(mngr, locked));
+        lockfile
+    }
+
+    #[test]
+    fn mismatches() {
+        let lockfile = lockfile("pip", &[("a", "1.0"), ("b", "2.0"), ("c", "3.0")]);
+        let pkgs = btreemap! {
+            ident("pip") => btreeset! { "a".to_string(), "b".to_string(), "c".to_string() },
+        };
+        let installed = versions("pip", &[("a", "1.0"), ("b", "2.1")]);
+        let mismatches: Vec<_> = lockfile
+            .mismatches(&pkgs, &installed)
+            .into_iter()
+            .map(|m| (m.pkg, m.locked, m.installed))
+            .collect();
+        assert_eq!(
+            mismatches,
+            vec![("b", "2.0", Some("2.1")), ("c", "3.0", None)]
+        );
+    }
+
+    #[test]
+    fn unlocked_pkgs_and_unqueried_managers_are_ignored() {
+        let mut lockfile = lockfile("pip", &[("a", "1.0")]);
+        lockfile.update(versions("npm", &[("b", "1.0")]));
+        let pkgs = btreemap! {
+            ident("pip") => btreeset! { "a".to_string(), "new".to_string() },
+            ident("npm") => btreeset! { "b".to_string() },
+        };
+        let installed = versions("pip", &[("a", "1.0"), ("new", "0.1")]);
+        assert!(lockfile.mismatches(&pkgs, &installed).is_empty());
+    }
+
+    #[test]
+    fn update_keeps_other_versions() {
+        let mut lockfile = lockfile("pip", &[("a", "1.0"), ("b", "1.0")]);
+        lockfile.update(versions("pip", &[("b", "2.0")]));
+        assert_eq!(
+            lockfile.versions,
+            versions("pip", &[("a", "1.0"), ("b", "2.0")])
+        );
+    }
+}
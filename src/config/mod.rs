@@ -2,6 +2,7 @@
 
 mod deps_graph;
 mod deserializers;
+mod lockfile;
 mod rule_actions;
 
 use crate::cli;
@@ -9,13 +10,19 @@ use crate::types::Identifier;
 use crate::os::{self, OSError};
 use deps_graph::DepsGraph;
 use maplit::hashmap;
-use rule_actions::{RuleActions, RuleActionsConf, RuleActionsError};
+use rule_actions::{ActionContext, ActionInfo, RuleActions, RuleActionsConf, RuleActionsError};
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fs::File;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
+pub use lockfile::Lockfile;
+pub use rule_actions::{
+    ActionsSelector, CollectedNotes, DecryptError, PkgVersions, PkgsBatch, PkgsByManager,
+    PkgsError,
+};
+
 /// Config file abstraction
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -39,6 +46,16 @@ pub enum ConfigError {
     #[error("Undefined rule: {0}")]
     UndefinedRule(Identifier),
 
+    #[error("Lockfile `{0}` not found, create it with `dotmake pkgs lock`")]
+    LockfileNotFound(PathBuf),
+
+    #[error("Invalid lockfile `{path}`: {err}")]
+    InvalidLockfile {
+        path: PathBuf,
+        #[source]
+        err: serde_json::Error,
+    },
+
     #[error(transparent)]
     OSError(#[from] OSError),
 }
@@ -107,6 +124,8 @@ impl Config {
             actions_conf: &self.actions_conf,
             actions,
             ident,
+            pkgs_batch: None,
+            notes: None,
         })
     }
 
@@ -117,6 +136,67 @@ impl Config {
             .ok_or_else(|| ConfigError::UndefinedRule(ident.clone()))
     }
 
+    /// Get identifiers of all the rules
+    pub fn rules(&self) -> impl Iterator<Item = &Identifier> {
+        self.rules.keys()
+    }
+
+    /// Collect packages of `pkgs` actions of given rules
+    pub fn pkgs_of<'a>(
+        &self,
+        rules: impl IntoIterator<Item = &'a Identifier>,
+    ) -> Result<PkgsByManager, ConfigError> {
+        let mut pkgs = PkgsByManager::new();
+        for rule in rules {
+            self.try_get_rule(rule)?.actions.add_pkgs_to(&mut pkgs);
+        }
+        Ok(pkgs)
+    }
+
+    /// Remove given packages using package managers' remove commands
+    pub fn remove_pkgs(&self, pkgs: &PkgsByManager) -> Result<(), OSError> {
+        rule_actions::remove_pkgs(&self.actions_conf, pkgs)
+    }
+
+    /// Replace packages' names with the ones used by their package managers on
+    /// the current distribution
+    pub fn resolve_pkg_names(&self, pkgs: &PkgsByManager) -> Result<PkgsByManager, OSError> {
+        rule_actions::resolve_pkg_names(&self.actions_conf, pkgs)
+    }
+
+    /// Get a single command, that installs all the given packages of a
+    /// package manager
+    pub fn pkgs_install_cmd(
+        &self,
+        mngr: &Identifier,
+        pkgs: &[String],
+    ) -> Result<String, PkgsError> {
+        rule_actions::install_cmd(&self.actions_conf, mngr, pkgs)
+    }
+
+    /// Get installed versions of given packages
+    pub fn pkg_versions(&self, pkgs: &PkgsByManager) -> Result<PkgVersions, OSError> {
+        rule_actions::pkg_versions(&self.actions_conf, pkgs)
+    }
+
+    /// Get path of a given file encrypted with the configured tool
+    pub fn encrypted_path(&self, file: &Path) -> PathBuf {
+        rule_actions::encrypted_path(&self.actions_conf, file)
+    }
+
+    /// Encrypt a file with the configured tool
+    pub fn encrypt(&self, file: &Path, dest: &Path) -> Result<(), DecryptError> {
+        rule_actions::encrypt(&self.actions_conf, file, dest)
+    }
+
+    /// Run upgrade commands of given package managers
+    pub fn upgrade_pkg_managers<'a>(
+        &self,
+        mngrs: impl Iterator<Item = &'a Identifier>,
+    ) -> Result<(), OSError> {
+        rule_actions::upgrade_pkg_managers(&self.actions_conf, mngrs)
+    }
+
     /// Build rules' depnedencies graph
     ///
     /// It's graranteed that all the nodes of resulting graph refer to existing
@@ -141,6 +221,8 @@ pub struct Rule<'a> {
     actions: &'a RuleActions,
     actions_conf: &'a RuleActionsConf,
     ident: &'a Identifier,
+    pkgs_batch: Option<&'a PkgsBatch>,
+    notes: Option<&'a CollectedNotes>,
 }
 
 /// Errors that can occure when interacting with [`Rule`]
@@ -152,21 +234,55 @@ pub enum RuleError {
         err: RuleActionsError,
         rule: Identifier,
     },
+
+    #[error("Failed to uninstall `{rule}`: {err}")]
+    FailedToUninstall {
+        #[source]
+        err: RuleActionsError,
+        rule: Identifier,
+    },
 }
 
 impl<'a> Rule<'a> {
+    /// Install packages of the rule from a given batch. Packages of every
+    /// package manager are installed when it's first used by any of the rules
+    /// and then skipped by the others
+    pub fn with_pkgs_batch(mut self, batch: Option<&'a PkgsBatch>) -> Self {
+        self.pkgs_batch = batch;
+        self
+    }
+
+    /// Collect notes left by the rule instead of printing them right away
+    pub fn with_notes(mut self, notes: Option<&'a CollectedNotes>) -> Self {
+        self.notes = notes;
+        self
+    }
+
+    /// Create a context, that the rule's actions are performed within
+    fn context(&self) -> ActionContext<'a> {
+        self.actions
+            .context(self.ident, self.actions_conf)
+            .with_pkgs_batch(self.pkgs_batch)
+            .with_notes(self.notes)
+    }
+
     /// Get rule's dependencies
     pub fn get_deps(&self) -> HashSet<Identifier> {
         self.actions.get_deps(&self.actions_conf)
     }
 
-    /// Perform nth (indexing from 1) action of the rule
+    /// Get short descriptions of the rule's actions
+    pub fn list_actions(&self) -> impl Iterator<Item = ActionInfo<'a>> {
+        self.actions.list()
+    }
+
+    /// Perform actions of the rule chosen by a given selector
     ///
-    /// This is a wrapper aroud [`RuleActions::perform_nth`]
-    pub fn perform_nth(&self, n: usize) -> Result<(), RuleError> {
+    /// This is a wrapper aroud [`RuleActions::perform_selected`]
+    pub fn perform_selected(&self, selector: &ActionsSelector) -> Result<(), RuleError> {
         Ok(self
             .actions
-            .perform_nth(n, self.actions_conf)
+            .perform_selected(selector, self.context())
             .map_err(|err| RuleError::FailedToPerform {
                 rule: self.ident.clone(),
                 err,
@@ -179,10 +295,22 @@ impl<'a> Rule<'a> {
     pub fn perform(&self) -> Result<(), RuleError> {
         Ok(self
             .actions
-            .perform(self.actions_conf)
+            .perform(self.context())
             .map_err(|err| RuleError::FailedToPerform {
                 rule: self.ident.clone(),
                 err,
             })?)
     }
+
+    /// Undo all the actions of the rule
+    ///
+    /// This is a wrapper aroud [`RuleActions::uninstall`]
+    pub fn uninstall(&self) -> Result<(), RuleError> {
+        self.actions
+            .uninstall(self.context())
+            .map_err(|err| RuleError::FailedToUninstall {
+                rule: self.ident.clone(),
+                err,
+            })
+    }
 }
@@ -0,0 +1,43 @@
+//! An action that copies dotfiles instead of linking them
+
+use super::{preview_files, prepare_dest, Action, ActionContext, DestState, FilesMap};
+use crate::cli;
+use crate::os;
+use std::env::set_current_dir;
+use std::error::Error;
+
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+pub struct Copy {
+    files: FilesMap,
+}
+
+impl Action for Copy {
+    fn perform(&self, ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        set_current_dir(cli::options().dotfiles_dir())?;
+        for (source, dests) in &self.files {
+            for dest in dests.iter() {
+                match prepare_dest(dest, ctx.conf, || Ok(os::same_contents(source, dest)?))? {
+                    DestState::UpToDate => continue,
+                    DestState::BackedUp(backup) => print_warn!(
+                        "`{}` has drifted from `{}`, the old copy is moved to `{}`",
+                        dest.display(),
+                        source.display(),
+                        backup.display()
+                    ),
+                    DestState::Ready => (),
+                }
+                if cli::options().dry_run() {
+                    print_info!("Would copy `{}` to `{}`", source.display(), dest.display());
+                    continue;
+                }
+                os::copy(source, dest)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        Some(preview_files(&self.files, false))
+    }
+}
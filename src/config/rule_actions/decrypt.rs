@@ -0,0 +1,200 @@
+//! An action that decrypts dotfiles, that can't be stored in plain text
+
+use super::{preview_files, prepare_dest, Action, ActionContext, DestState, FilesMap};
+use super::RuleActionsConf;
+use crate::cli;
+use crate::os::{self, OSError};
+use crate::types::UserPath;
+use std::env::set_current_dir;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use thiserror::Error;
+
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+pub struct Decrypt {
+    files: FilesMap,
+}
+
+/// Encryption settings used by `decrypt` action and `dotmake add --encrypt`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptionConf {
+    /// A tool, that encrypts files added by `dotmake add --encrypt`
+    #[serde(default)]
+    tool: EncryptionTool,
+
+    /// Recipients files are encrypted for. `gpg` uses the default key if
+    /// there are none
+    #[serde(default)]
+    recipients: Vec<String>,
+
+    /// Identity file, that `age` decrypts files with
+    #[serde(default)]
+    identity: Option<UserPath>,
+}
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum EncryptionTool {
+    #[default]
+    Gpg,
+    Age,
+}
+
+impl EncryptionTool {
+    /// Guess a tool, that a file is encrypted with, by its extension
+    fn of_file(file: &Path) -> Option<Self> {
+        match file.extension()?.to_str()? {
+            "gpg" | "asc" | "pgp" => Some(EncryptionTool::Gpg),
+            "age" => Some(EncryptionTool::Age),
+            _ => None,
+        }
+    }
+
+    /// Extension of files encrypted with the tool
+    fn extension(self) -> &'static str {
+        match self {
+            EncryptionTool::Gpg => "gpg",
+            EncryptionTool::Age => "age",
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DecryptError {
+    #[error("Unknown encryption of `{0}`, expected `.gpg`, `.asc`, `.pgp` or `.age` file")]
+    UnknownEncryption(PathBuf),
+
+    #[error("`age` needs `conf.encryption.identity` to decrypt files")]
+    NoIdentity,
+
+    #[error("`age` needs `conf.encryption.recipients` to encrypt files")]
+    NoRecipients,
+
+    #[error("Failed to decrypt `{file}`: {err}")]
+    FailedToDecrypt {
+        file: PathBuf,
+        #[source]
+        err: OSError,
+    },
+
+    #[error("Failed to encrypt `{file}`: {err}")]
+    FailedToEncrypt {
+        file: PathBuf,
+        #[source]
+        err: OSError,
+    },
+}
+
+impl EncryptionConf {
+    /// Decrypt a file and get its contents
+    fn decrypt(&self, file: &Path) -> Result<Vec<u8>, DecryptError> {
+        let tool = EncryptionTool::of_file(file)
+            .ok_or_else(|| DecryptError::UnknownEncryption(file.to_owned()))?;
+        let mut cmd;
+        match tool {
+            EncryptionTool::Gpg => {
+                cmd = Command::new("gpg");
+                cmd.arg("--quiet").arg("--decrypt");
+            }
+            EncryptionTool::Age => {
+                let identity = self.identity.as_ref().ok_or(DecryptError::NoIdentity)?;
+                cmd = Command::new("age");
+                cmd.arg("--decrypt").arg("--identity").arg(&**identity);
+            }
+        }
+        cmd.arg(file).stdin(Stdio::inherit());
+        os::command_stdout(&mut cmd).map_err(|err| DecryptError::FailedToDecrypt {
+            file: file.to_owned(),
+            err,
+        })
+    }
+
+    /// Encrypt a file with the configured tool
+    fn encrypt(&self, file: &Path, dest: &Path) -> Result<(), DecryptError> {
+        let mut cmd;
+        match self.tool {
+            EncryptionTool::Gpg => {
+                cmd = Command::new("gpg");
+                cmd.arg("--yes").arg("--output").arg(dest).arg("--encrypt");
+                if self.recipients.is_empty() {
+                    cmd.arg("--default-recipient-self");
+                }
+                for recipient in &self.recipients {
+                    cmd.arg("--recipient").arg(recipient);
+                }
+            }
+            EncryptionTool::Age => {
+                if self.recipients.is_empty() {
+                    return Err(DecryptError::NoRecipients);
+                }
+                cmd = Command::new("age");
+                cmd.arg("--encrypt").arg("--output").arg(dest);
+                for recipient in &self.recipients {
+                    cmd.arg("--recipient").arg(recipient);
+                }
+            }
+        }
+        cmd.arg(file);
+        os::ensure_parent_dir(dest).map_err(|err| DecryptError::FailedToEncrypt {
+            file: file.to_owned(),
+            err,
+        })?;
+        os::run_command(&mut cmd).map_err(|err| DecryptError::FailedToEncrypt {
+            file: file.to_owned(),
+            err,
+        })
+    }
+}
+
+/// Get path of a given file, encrypted with the configured tool, i.e. the
+/// file's path with the tool's extension appended
+pub fn encrypted_path(conf: &RuleActionsConf, file: &Path) -> PathBuf {
+    let mut path = file.as_os_str().to_owned();
+    path.push(".");
+    path.push(conf.encryption.tool.extension());
+    PathBuf::from(path)
+}
+
+/// Encrypt a file with the configured tool
+pub fn encrypt(conf: &RuleActionsConf, file: &Path, dest: &Path) -> Result<(), DecryptError> {
+    conf.encryption.encrypt(file, dest)
+}
+
+impl Action for Decrypt {
+    fn perform(&self, ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        set_current_dir(cli::options().dotfiles_dir())?;
+        for (source, dests) in &self.files {
+            if cli::options().dry_run() {
+                for dest in dests.iter() {
+                    print_info!(
+                        "Would decrypt `{}` to `{}`",
+                        source.display(),
+                        dest.display()
+                    );
+                }
+                continue;
+            }
+
+            let decrypted = ctx.conf.encryption.decrypt(source)?;
+            for dest in dests.iter() {
+                let up_to_date = || Ok(fs::read(dest.as_path())? == decrypted);
+                if let DestState::UpToDate = prepare_dest(dest, ctx.conf, up_to_date)? {
+                    if os::get_mode(dest)?.bits() != 0o600 {
+                        os::set_mode(dest, 0o600.into())?;
+                    }
+                    continue;
+                }
+                os::write_private_file(dest, &decrypted)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        Some(preview_files(&self.files, false))
+    }
+}
@@ -1,6 +1,6 @@
 //! An action that only specifies dependencies
 
-use super::{Action, RuleActionsConf};
+use super::{Action, ActionContext, RuleActionsConf};
 use crate::types::{Identifier, Identifiers};
 use std::collections::HashSet;
 
@@ -14,4 +14,9 @@ impl Action for Deps {
     fn get_deps(&self, _conf: &RuleActionsConf) -> HashSet<Identifier> {
         self.deps.into_iter().collect()
     }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        let deps: Vec<_> = self.deps.into_iter().map(|dep| dep.to_string()).collect();
+        Some(format!("Depends on: {}", deps.join(", ")))
+    }
 }
@@ -0,0 +1,187 @@
+//! An action that downloads files
+
+use super::{Action, ActionContext, RuleActionsConf};
+use crate::cli;
+use crate::os;
+use crate::types::{Mode, UserPath};
+use std::env::set_current_dir;
+use std::error::Error;
+use std::process::Command;
+use tempdir::TempDir;
+use thiserror::Error;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Download {
+    url: String,
+    dest: UserPath,
+
+    /// Expected SHA-256 checksum of the file
+    #[serde(default)]
+    sha256: Option<String>,
+
+    #[serde(default)]
+    mode: Option<Mode>,
+}
+
+#[derive(Debug, Error)]
+pub enum DownloadError {
+    #[error("Checksum mismatch for `{url}`: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl Download {
+    /// Test, whether or not the destination is already downloaded. Without a
+    /// checksum any existing file is considered to be up to date
+    fn is_up_to_date(&self) -> Result<bool, Box<dyn Error>> {
+        if !self.dest.exists() {
+            return Ok(false);
+        }
+        Ok(match &self.sha256 {
+            Some(sha256) => os::sha256_file(&self.dest)? == sha256.to_lowercase(),
+            None => true,
+        })
+    }
+
+    /// Download the file to a temporary directory, verify it's checksum and
+    /// then copy it to the destination
+    fn download(&self, conf: &RuleActionsConf) -> Result<(), Box<dyn Error>> {
+        let temp_dir = TempDir::new("dotmake")?;
+        let temp_file = temp_dir.path().join("download");
+        os::run_command(
+            Command::new("curl")
+                .args(["--fail", "--location", "--silent", "--show-error"])
+                .arg("--output")
+                .arg(&temp_file)
+                .arg(&self.url),
+        )?;
+
+        if let Some(sha256) = &self.sha256 {
+            let actual = os::sha256_file(&temp_file)?;
+            if actual != sha256.to_lowercase() {
+                Err(DownloadError::ChecksumMismatch {
+                    url: self.url.clone(),
+                    expected: sha256.clone(),
+                    actual,
+                })?;
+            }
+        }
+
+        if self.dest.exists() {
+            os::backup(&self.dest, &conf.backup_dir)?;
+        }
+        os::copy(&temp_file, &self.dest)?;
+        temp_dir.close()?;
+        Ok(())
+    }
+}
+
+impl Action for Download {
+    fn perform(&self, ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        set_current_dir(cli::options().dotfiles_dir())?;
+        let dry_run = cli::options().dry_run();
+        if self.is_up_to_date()? {
+            print_info!("`{}` is up to date", self.dest.display());
+        } else {
+            print_info!("Downloading `{}` to `{}`", self.url, self.dest.display());
+            if !dry_run {
+                self.download(ctx.conf)?;
+            }
+        }
+
+        if let Some(mode) = self.mode {
+            if self.dest.exists() && os::get_mode(&self.dest)? != mode {
+                print_info!("Changing mode of `{}` to {}", self.dest.display(), mode);
+                if !dry_run {
+                    os::set_mode(&self.dest, mode)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        Some(format!("{} -> {}", self.url, self.dest.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Download, DownloadError, RuleActionsConf};
+    use crate::os;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tempdir::TempDir;
+
+    /// Configuration, that only sets a given backup directory
+    fn conf(backup_dir: &Path) -> RuleActionsConf {
+        let conf = format!(
+            "{{shell: sh, backup_dir: '{}', pkg_managers: {{install_cmds: {{}}, deps: {{}}}}}}",
+            backup_dir.display()
+        );
+        serde_yaml::from_str(&conf).unwrap()
+    }
+
+    /// Create a file to download
+    fn source(dir: &TempDir) -> PathBuf {
+        let source = dir.path().join("source");
+        fs::write(&source, "contents").unwrap();
+        source
+    }
+
+    /// Get a `download` action for a created file
+    fn download(dir: &TempDir, sha256: Option<&str>) -> Download {
+        let source = source(dir);
+        Download {
+            url: format!("file://{}", source.display()),
+            dest: dir.path().join("dest").to_str().unwrap().parse().unwrap(),
+            sha256: sha256.map(str::to_string),
+            mode: None,
+        }
+    }
+
+    #[test]
+    fn checksum_is_verified() {
+        let dir = TempDir::new("dotmake-test").unwrap();
+        let sha256 = os::sha256_file(source(&dir)).unwrap();
+        let download = download(&dir, Some(&sha256.to_uppercase()));
+        assert!(!download.is_up_to_date().unwrap());
+
+        download
+            .download(&conf(&dir.path().join("backup")))
+            .unwrap();
+        assert_eq!(fs::read_to_string(&*download.dest).unwrap(), "contents");
+        assert!(download.is_up_to_date().unwrap());
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let dir = TempDir::new("dotmake-test").unwrap();
+        let download = download(&dir, Some("0123"));
+        let err = download
+            .download(&conf(&dir.path().join("backup")))
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(DownloadError::ChecksumMismatch { .. })
+        ));
+        assert!(!download.dest.exists());
+    }
+
+    #[test]
+    fn existing_file_is_backed_up() {
+        let dir = TempDir::new("dotmake-test").unwrap();
+        let download = download(&dir, None);
+        fs::write(&*download.dest, "old").unwrap();
+        assert!(download.is_up_to_date().unwrap());
+
+        let backup_dir = dir.path().join("backup");
+        download.download(&conf(&backup_dir)).unwrap();
+        assert_eq!(fs::read_to_string(&*download.dest).unwrap(), "contents");
+        assert_eq!(fs::read_to_string(backup_dir.join("dest")).unwrap(), "old");
+    }
+}
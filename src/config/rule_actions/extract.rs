@@ -0,0 +1,194 @@
+//! An action that extracts archives
+
+use super::{Action, ActionContext};
+use crate::cli;
+use crate::os::{self, OSError};
+use crate::types::UserPath;
+use std::env::set_current_dir;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempdir::TempDir;
+use thiserror::Error;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Extract {
+    archive: UserPath,
+    dest: UserPath,
+
+    /// Number of leading path components to remove from extracted files
+    #[serde(default)]
+    strip_components: usize,
+}
+
+#[derive(Debug, Error)]
+pub enum ExtractError {
+    #[error("Unsupported archive format of `{0}`")]
+    UnsupportedFormat(PathBuf),
+}
+
+/// Extensions of archives, that are extracted with `tar`
+const TAR_EXTENSIONS: &[&str] = &[".tar", ".tar.gz", ".tgz", ".tar.xz", ".txz", ".tar.bz2"];
+
+impl Extract {
+    /// Path of a file in the destination directory, that stores checksum of
+    /// the extracted archive
+    fn marker_path(&self) -> Result<PathBuf, OSError> {
+        let mut name = ".dotmake-extracted-".to_string();
+        name.push_str(&os::get_file_name(&*self.archive)?.to_string_lossy());
+        Ok(self.dest.join(name))
+    }
+
+    fn extract(&self) -> Result<(), Box<dyn Error>> {
+        let name = self.archive.to_string_lossy();
+        if TAR_EXTENSIONS.iter().any(|ext| name.ends_with(ext)) {
+            os::run_command(
+                Command::new("tar")
+                    .arg("-xf")
+                    .arg(&*self.archive)
+                    .arg(format!("--strip-components={}", self.strip_components))
+                    .arg("-C")
+                    .arg(&*self.dest),
+            )?;
+        } else if name.ends_with(".zip") {
+            // `unzip` can't strip components, so the archive is extracted to
+            // a temporary directory first
+            let temp_dir = TempDir::new("dotmake")?;
+            os::run_command(
+                Command::new("unzip")
+                    .arg("-q")
+                    .arg(&*self.archive)
+                    .arg("-d")
+                    .arg(temp_dir.path()),
+            )?;
+            for entry in strip_components(temp_dir.path(), self.strip_components)? {
+                let relative = entry.strip_prefix(temp_dir.path())?;
+                let relative: PathBuf = relative.components().skip(self.strip_components).collect();
+                os::copy(&entry, self.dest.join(relative))?;
+            }
+            temp_dir.close()?;
+        } else {
+            Err(ExtractError::UnsupportedFormat(self.archive.to_path_buf()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Get all the entries of a given directory, that are placed `n` levels deep in
+/// it. Entries placed less deep are ignored. Symlinks to directories aren't
+/// followed
+fn strip_components(dir: &Path, n: usize) -> Result<Vec<PathBuf>, OSError> {
+    let read_err = |err| OSError::IO {
+        msg: format!("Failed to read `{}`", dir.display()),
+        err,
+    };
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir).map_err(read_err)? {
+        let path = entry.map_err(read_err)?.path();
+        if n == 0 {
+            entries.push(path);
+        } else if fs::symlink_metadata(&path).map_err(read_err)?.is_dir() {
+            entries.extend(strip_components(&path, n - 1)?);
+        }
+    }
+    Ok(entries)
+}
+
+impl Action for Extract {
+    fn perform(&self, _ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        set_current_dir(cli::options().dotfiles_dir())?;
+        let checksum = os::sha256_file(&self.archive)?;
+        let marker = self.marker_path()?;
+        if marker.exists() && os::read_to_string(&marker)?.trim() == checksum {
+            print_info!("`{}` is up to date", self.dest.display());
+            return Ok(());
+        }
+
+        print_info!(
+            "Extracting `{}` to `{}`",
+            self.archive.display(),
+            self.dest.display()
+        );
+        if !cli::options().dry_run() {
+            os::ensure_dir_exists(&self.dest)?;
+            self.extract()?;
+            os::write_file(&marker, format!("{}\n", checksum))?;
+        }
+        Ok(())
+    }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        Some(format!(
+            "{} -> {}",
+            self.archive.display(),
+            self.dest.display()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip_components;
+    use std::fs;
+    use std::os::unix::fs::symlink;
+    use std::path::{Path, PathBuf};
+    use tempdir::TempDir;
+
+    /// Create a directory tree with given files
+    fn tree(files: &[&str]) -> TempDir {
+        let dir = TempDir::new("dotmake-test").unwrap();
+        for file in files {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        dir
+    }
+
+    fn stripped(dir: &Path, n: usize) -> Vec<PathBuf> {
+        let mut entries: Vec<_> = strip_components(dir, n)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.strip_prefix(dir).unwrap().to_owned())
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn no_components() {
+        let dir = tree(&["a/b", "c"]);
+        assert_eq!(
+            stripped(dir.path(), 0),
+            vec![Path::new("a"), Path::new("c")]
+        );
+    }
+
+    #[test]
+    fn top_level_dir() {
+        let dir = tree(&["pkg-1.0/bin/tool", "pkg-1.0/README"]);
+        assert_eq!(
+            stripped(dir.path(), 1),
+            vec![Path::new("pkg-1.0/README"), Path::new("pkg-1.0/bin")]
+        );
+    }
+
+    #[test]
+    fn symlinks_to_dirs_are_not_followed() {
+        let dir = tree(&["pkg/lib/a"]);
+        symlink("lib", dir.path().join("pkg/lib-link")).unwrap();
+        symlink(".", dir.path().join("pkg/lib/loop")).unwrap();
+        assert_eq!(
+            stripped(dir.path(), 2),
+            vec![Path::new("pkg/lib/a"), Path::new("pkg/lib/loop")]
+        );
+    }
+
+    #[test]
+    fn shallow_entries_are_ignored() {
+        let dir = tree(&["a/b/c", "a/d", "e"]);
+        assert_eq!(stripped(dir.path(), 2), vec![Path::new("a/b/c")]);
+    }
+}
@@ -0,0 +1,231 @@
+//! An action that clones or updates git repositories
+
+use super::{Action, ActionContext};
+use crate::cli;
+use crate::os::{self, OSError};
+use crate::types::UserPath;
+use std::env::set_current_dir;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use thiserror::Error;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Git {
+    repo: String,
+    dest: UserPath,
+
+    /// A branch, a tag or a commit to check out
+    #[serde(default, rename = "ref")]
+    reference: Option<String>,
+
+    /// Whether to fetch and fast-forward an already cloned repository
+    #[serde(default = "Git::default_update")]
+    update: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum GitError {
+    #[error("`{0}` exists but is not a git repository")]
+    NotARepository(PathBuf),
+
+    #[error("`{0}` has local changes, refusing to update it")]
+    LocalChanges(PathBuf),
+}
+
+impl Git {
+    fn default_update() -> bool {
+        true
+    }
+
+    /// Create `git` command running in a given repository
+    fn git(repo: &Path) -> Command {
+        let mut cmd = Command::new("git");
+        cmd.arg("-C").arg(repo);
+        cmd
+    }
+
+    /// Test, whether or not a given git command succeeds without printing
+    /// anything
+    fn git_succeeds(repo: &Path, args: &[&str]) -> bool {
+        let status = Self::git(repo)
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        matches!(status, Ok(status) if status.success())
+    }
+
+    fn clone(&self, dry_run: bool) -> Result<(), OSError> {
+        print_info!("Cloning `{}` to `{}`", self.repo, self.dest.display());
+        if dry_run {
+            return Ok(());
+        }
+        os::ensure_parent_dir(&self.dest)?;
+        os::run_command(Command::new("git").arg("clone").arg(&self.repo).arg(&*self.dest))?;
+        if let Some(reference) = &self.reference {
+            os::run_command(Self::git(&self.dest).args(["checkout", "-q", reference]))?;
+        }
+        Ok(())
+    }
+
+    fn update(&self, dry_run: bool) -> Result<(), Box<dyn Error>> {
+        let dest: &Path = &self.dest;
+        if !dest.join(".git").exists() {
+            Err(GitError::NotARepository(dest.to_owned()))?;
+        }
+        let status = os::command_output(Self::git(dest).args(["status", "--porcelain", "-uno"]))?;
+        if !status.trim().is_empty() {
+            Err(GitError::LocalChanges(dest.to_owned()))?;
+        }
+
+        print_info!("Updating `{}`", dest.display());
+        if dry_run {
+            return Ok(());
+        }
+        os::run_command(Self::git(dest).args(["fetch", "-q", "--tags", "origin"]))?;
+        if let Some(reference) = &self.reference {
+            os::run_command(Self::git(dest).args(["checkout", "-q", reference]))?;
+        }
+        let on_branch = Self::git_succeeds(dest, &["symbolic-ref", "-q", "HEAD"]);
+        if on_branch && Self::git_succeeds(dest, &["rev-parse", "@{upstream}"]) {
+            os::run_command(Self::git(dest).args(["merge", "-q", "--ff-only", "@{upstream}"]))?;
+        }
+        Ok(())
+    }
+}
+
+impl Action for Git {
+    fn perform(&self, _ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        set_current_dir(cli::options().dotfiles_dir())?;
+        let dry_run = cli::options().dry_run();
+        if !self.dest.exists() {
+            self.clone(dry_run)?;
+        } else if self.update {
+            self.update(dry_run)?;
+        }
+        Ok(())
+    }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        let reference = match &self.reference {
+            Some(reference) => format!(" ({})", reference),
+            None => String::new(),
+        };
+        Some(format!("{}{} -> {}", self.repo, reference, self.dest.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Git, GitError};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use tempdir::TempDir;
+
+    /// Run git in a given repository
+    fn git(repo: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    /// Commit a file to a given repository
+    fn commit(repo: &Path, file: &str, contents: &str) {
+        fs::write(repo.join(file), contents).unwrap();
+        git(repo, &["add", file]);
+        git(repo, &["commit", "-q", "-m", file]);
+    }
+
+    /// Create a repository with a single commit
+    fn origin(dir: &TempDir) -> PathBuf {
+        let origin = dir.path().join("origin");
+        fs::create_dir(&origin).unwrap();
+        git(&origin, &["init", "-q"]);
+        commit(&origin, "a", "1");
+        origin
+    }
+
+    fn action(origin: &Path, dest: &Path, reference: Option<&str>) -> Git {
+        Git {
+            repo: format!("file://{}", origin.display()),
+            dest: dest.to_str().unwrap().parse().unwrap(),
+            reference: reference.map(str::to_string),
+            update: true,
+        }
+    }
+
+    #[test]
+    fn clone_and_update() {
+        let dir = TempDir::new("dotmake-test").unwrap();
+        let origin = origin(&dir);
+        let dest = dir.path().join("clone");
+        let git = action(&origin, &dest, None);
+
+        git.clone(false).unwrap();
+        assert_eq!(fs::read_to_string(dest.join("a")).unwrap(), "1");
+
+        commit(&origin, "b", "2");
+        git.update(false).unwrap();
+        assert_eq!(fs::read_to_string(dest.join("b")).unwrap(), "2");
+    }
+
+    #[test]
+    fn clone_ref() {
+        let dir = TempDir::new("dotmake-test").unwrap();
+        let origin = origin(&dir);
+        git(&origin, &["tag", "v1"]);
+        commit(&origin, "a", "2");
+        let dest = dir.path().join("clone");
+
+        action(&origin, &dest, Some("v1")).clone(false).unwrap();
+        assert_eq!(fs::read_to_string(dest.join("a")).unwrap(), "1");
+    }
+
+    #[test]
+    fn dry_run() {
+        let dir = TempDir::new("dotmake-test").unwrap();
+        let origin = origin(&dir);
+        let dest = dir.path().join("clone");
+
+        action(&origin, &dest, None).clone(true).unwrap();
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn local_changes_are_kept() {
+        let dir = TempDir::new("dotmake-test").unwrap();
+        let origin = origin(&dir);
+        let dest = dir.path().join("clone");
+        let git = action(&origin, &dest, None);
+        git.clone(false).unwrap();
+        fs::write(dest.join("a"), "changed").unwrap();
+
+        let err = git.update(false).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(GitError::LocalChanges(_))
+        ));
+        assert_eq!(fs::read_to_string(dest.join("a")).unwrap(), "changed");
+    }
+
+    #[test]
+    fn not_a_repository() {
+        let dir = TempDir::new("dotmake-test").unwrap();
+        let err = action(&origin(&dir), dir.path(), None)
+            .update(false)
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(GitError::NotARepository(_))
+        ));
+    }
+}
@@ -0,0 +1,307 @@
+//! Actions that manage parts of files, that can't be fully owned by dotfiles
+
+use super::{Action, ActionContext, RuleActionsConf};
+use crate::cli;
+use crate::os::{self, OSError};
+use crate::state::State;
+use crate::types::UserPath;
+use regex::Regex;
+use std::env::set_current_dir;
+use std::error::Error;
+use std::iter;
+use std::path::Path;
+
+/// Ensure that a line is present in a file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LineInFile {
+    path: UserPath,
+    line: String,
+
+    /// A regex matching a line that should be replaced with `line`, if it's not
+    /// present in the file yet
+    #[serde(default)]
+    regex: Option<String>,
+}
+
+impl LineInFile {
+    /// Add the line, unless it's present. Returns the line it has replaced,
+    /// if any
+    fn ensure(&self, lines: &mut Vec<String>) -> Result<Option<String>, regex::Error> {
+        if lines.contains(&self.line) {
+            return Ok(None);
+        }
+        if let Some(regex) = &self.regex {
+            let regex = Regex::new(regex)?;
+            if let Some(line) = lines.iter_mut().find(|line| regex.is_match(line)) {
+                return Ok(Some(std::mem::replace(line, self.line.clone())));
+            }
+        }
+        lines.push(self.line.clone());
+        Ok(None)
+    }
+}
+
+impl Action for LineInFile {
+    fn perform(&self, ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        set_current_dir(cli::options().dotfiles_dir())?;
+        let old = read_lines(&self.path)?;
+        let mut new = old.clone();
+        let replaced = self.ensure(&mut new)?;
+        let mut state = State::load()?;
+        update_file(&self.path, &old, &new, &state, ctx.conf)?;
+        if old == new || cli::options().dry_run() {
+            return Ok(());
+        }
+
+        // A replaced line, that was added by dotmake as well, has replaced the
+        // user's one in its turn
+        let replaced =
+            replaced.map(|line| state.take_line(&self.path, &line).unwrap_or(Some(line)));
+        state.add_line(&self.path, self.line.clone(), replaced.flatten());
+        Ok(state.save()?)
+    }
+
+    fn uninstall(&self, _ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        set_current_dir(cli::options().dotfiles_dir())?;
+        let mut state = State::load()?;
+        let replaced = match state.take_line(&self.path, &self.line) {
+            Some(replaced) => replaced,
+            None => {
+                print_info!(
+                    "The line wasn't added to `{}` by dotmake, leaving it",
+                    self.path.display()
+                );
+                return Ok(());
+            }
+        };
+        let mut lines = read_lines(&self.path)?;
+        if let Some(pos) = lines.iter().position(|line| line == &self.line) {
+            if cli::options().dry_run() {
+                match replaced {
+                    Some(_) => print_info!(
+                        "Would restore the replaced line in `{}`",
+                        self.path.display()
+                    ),
+                    None => print_info!("Would remove the line from `{}`", self.path.display()),
+                }
+                return Ok(());
+            }
+            match replaced {
+                Some(replaced) => lines[pos] = replaced,
+                None => drop(lines.remove(pos)),
+            }
+            write_lines(&self.path, &lines)?;
+        }
+        Ok(state.save()?)
+    }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        Some(format!("{}: {}", self.path.display(), self.line))
+    }
+}
+
+/// Ensure that a block of lines surrounded by marker lines is present in a
+/// file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockInFile {
+    path: UserPath,
+    block: String,
+
+    /// Marker line template. `{mark}` is replaced with `BEGIN` and `END` for
+    /// the opening and closing markers respectively
+    #[serde(default = "BlockInFile::default_marker")]
+    marker: String,
+}
+
+impl BlockInFile {
+    fn default_marker() -> String {
+        "# {mark} DOTMAKE MANAGED BLOCK".to_string()
+    }
+
+    fn marker(&self, mark: &str) -> String {
+        self.marker.replace("{mark}", mark)
+    }
+
+    /// Find position of the block (including markers), if it exists
+    fn find(&self, lines: &[String]) -> Option<(usize, usize)> {
+        let (begin, end) = (self.marker("BEGIN"), self.marker("END"));
+        let start = lines.iter().position(|line| line == &begin)?;
+        let len = lines[start..].iter().position(|line| line == &end)?;
+        Some((start, start + len))
+    }
+
+    fn ensure(&self, lines: &mut Vec<String>) {
+        let block = iter::once(self.marker("BEGIN"))
+            .chain(self.block.lines().map(str::to_string))
+            .chain(iter::once(self.marker("END")));
+        match self.find(lines) {
+            Some((start, end)) => drop(lines.splice(start..=end, block)),
+            None => lines.extend(block),
+        }
+    }
+}
+
+impl Action for BlockInFile {
+    fn perform(&self, ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        set_current_dir(cli::options().dotfiles_dir())?;
+        let old = read_lines(&self.path)?;
+        let mut new = old.clone();
+        self.ensure(&mut new);
+        let mut state = State::load()?;
+        update_file(&self.path, &old, &new, &state, ctx.conf)?;
+        if old == new || cli::options().dry_run() {
+            return Ok(());
+        }
+        state.add_edited(&self.path);
+        Ok(state.save()?)
+    }
+
+    fn uninstall(&self, _ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        set_current_dir(cli::options().dotfiles_dir())?;
+        let mut lines = read_lines(&self.path)?;
+        if let Some((start, end)) = self.find(&lines) {
+            if cli::options().dry_run() {
+                print_info!("Would remove the block from `{}`", self.path.display());
+                return Ok(());
+            }
+            lines.drain(start..=end);
+            write_lines(&self.path, &lines)?;
+        }
+        Ok(())
+    }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        Some(format!("{}:\n{}", self.path.display(), self.block))
+    }
+}
+
+/// Write new lines to a file, if they differ from the old ones. An existing
+/// file is backed up before it's first edited by dotmake. With `--dry-run` the
+/// file is left as is
+fn update_file(
+    path: &Path,
+    old: &[String],
+    new: &[String],
+    state: &State,
+    conf: &RuleActionsConf,
+) -> Result<(), Box<dyn Error>> {
+    if old == new {
+        print_info!("`{}` is up to date", path.display());
+        return Ok(());
+    }
+    if cli::options().dry_run() {
+        print_info!("Would update `{}`", path.display());
+        return Ok(());
+    }
+    if !state.is_edited(path) && path.exists() {
+        let backup = os::backup_copy(path, &conf.backup_dir)?;
+        print_info!(
+            "Original `{}` is copied to `{}`",
+            path.display(),
+            backup.display()
+        );
+    }
+    Ok(write_lines(path, new)?)
+}
+
+/// Read lines of a file. If the file doesn't exist, no lines are returned
+fn read_lines(path: &Path) -> Result<Vec<String>, OSError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(os::read_to_string(path)?
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Write lines to a file, terminating every line with a newline
+fn write_lines(path: &Path, lines: &[String]) -> Result<(), OSError> {
+    let contents: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+    os::write_file(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    fn line_in_file(line: &str, regex: Option<&str>) -> LineInFile {
+        LineInFile {
+            path: "file".parse().unwrap(),
+            line: line.to_string(),
+            regex: regex.map(str::to_string),
+        }
+    }
+
+    fn block_in_file(block: &str) -> BlockInFile {
+        BlockInFile {
+            path: "file".parse().unwrap(),
+            block: block.to_string(),
+            marker: BlockInFile::default_marker(),
+        }
+    }
+
+    #[test]
+    fn present_line_is_kept() {
+        let mut file = lines(&["a", "b"]);
+        let replaced = line_in_file("a", Some("b")).ensure(&mut file).unwrap();
+        assert_eq!(replaced, None);
+        assert_eq!(file, lines(&["a", "b"]));
+    }
+
+    #[test]
+    fn first_matching_line_is_replaced() {
+        let mut file = lines(&["a", "x=1", "x=2"]);
+        let replaced = line_in_file("x=3", Some("^x=")).ensure(&mut file).unwrap();
+        assert_eq!(replaced.as_deref(), Some("x=1"));
+        assert_eq!(file, lines(&["a", "x=3", "x=2"]));
+    }
+
+    #[test]
+    fn missing_line_is_appended() {
+        let mut file = lines(&["a"]);
+        let replaced = line_in_file("b", Some("^c")).ensure(&mut file).unwrap();
+        assert_eq!(replaced, None);
+        assert_eq!(file, lines(&["a", "b"]));
+    }
+
+    #[test]
+    fn block_is_found_with_markers() {
+        let file = lines(&[
+            "a",
+            "# BEGIN DOTMAKE MANAGED BLOCK",
+            "b",
+            "# END DOTMAKE MANAGED BLOCK",
+            "c",
+        ]);
+        assert_eq!(block_in_file("b").find(&file), Some((1, 3)));
+        assert_eq!(block_in_file("b").find(&file[..3]), None);
+    }
+
+    #[test]
+    fn block_is_replaced() {
+        let mut file = lines(&[
+            "# BEGIN DOTMAKE MANAGED BLOCK",
+            "old",
+            "# END DOTMAKE MANAGED BLOCK",
+            "c",
+        ]);
+        block_in_file("x\ny").ensure(&mut file);
+        assert_eq!(
+            file,
+            lines(&[
+                "# BEGIN DOTMAKE MANAGED BLOCK",
+                "x",
+                "y",
+                "# END DOTMAKE MANAGED BLOCK",
+                "c",
+            ])
+        );
+    }
+}
@@ -0,0 +1,82 @@
+//! An action that creates directories
+
+use super::{Action, ActionContext};
+use crate::cli;
+use crate::config::deserializers::{List, ShortOrFull};
+use crate::os;
+use crate::types::{Mode, UserPath};
+use std::env::set_current_dir;
+use std::error::Error;
+
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+pub struct Mkdir {
+    dirs: List<Dir>,
+}
+
+/// A directory, that is specified either by it's path or by a path along with
+/// a mode
+#[derive(Debug, Deserialize)]
+#[serde(from = "ShortOrFull<UserPath, DirWithMode>")]
+struct Dir {
+    path: UserPath,
+    mode: Option<Mode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DirWithMode {
+    path: UserPath,
+    mode: Mode,
+}
+
+impl From<ShortOrFull<UserPath, DirWithMode>> for Dir {
+    fn from(dir: ShortOrFull<UserPath, DirWithMode>) -> Self {
+        match dir {
+            ShortOrFull::Short(path) => Dir { path, mode: None },
+            ShortOrFull::Full(dir) => Dir {
+                path: dir.path,
+                mode: Some(dir.mode),
+            },
+        }
+    }
+}
+
+impl Action for Mkdir {
+    fn perform(&self, _ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        set_current_dir(cli::options().dotfiles_dir())?;
+        let dry_run = cli::options().dry_run();
+        for dir in self.dirs.iter() {
+            let path = &dir.path;
+            if !path.is_dir() {
+                print_info!("Creating `{}`", path.display());
+                if !dry_run {
+                    os::ensure_dir_exists(path)?;
+                }
+            }
+
+            if let Some(mode) = dir.mode {
+                let old_mode = if path.exists() {
+                    Some(os::get_mode(path)?)
+                } else {
+                    None
+                };
+                if old_mode != Some(mode) {
+                    print_info!("Changing mode of `{}` to {}", path.display(), mode);
+                    if !dry_run {
+                        os::set_mode(path, mode)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        let dirs = self.dirs.iter().map(|dir| match dir.mode {
+            Some(mode) => format!("{} ({})", dir.path.display(), mode),
+            None => dir.path.display().to_string(),
+        });
+        Some(dirs.collect::<Vec<_>>().join("\n"))
+    }
+}
@@ -1,29 +1,97 @@
 //! Rule actions that specify rule's dependencies and behavior
 
+mod copy;
+mod decrypt;
 mod deps;
+mod download;
+mod extract;
+mod git;
+mod in_file;
 mod links;
+mod mkdir;
+mod notes;
+mod permissions;
 mod pkgs;
+mod prompt;
+mod run;
 mod shell_script;
+mod template;
 
+use crate::cli;
+use crate::config::deserializers::{List, ShortOrFull};
+use crate::io::select;
+use crate::os::{self, OSError};
 use crate::types::UserPath;
 use crate::types::Identifier;
-use std::collections::HashSet;
+use itertools::Itertools;
+use serde::de::{
+    self, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess, VariantAccess,
+    Visitor,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use thiserror::Error;
 
+use copy::Copy;
+use decrypt::{Decrypt, EncryptionConf};
+pub use decrypt::{encrypt, encrypted_path, DecryptError};
 use deps::Deps;
+use download::Download;
+use extract::Extract;
+use git::Git;
+use in_file::{BlockInFile, LineInFile};
 use links::Links;
+use mkdir::Mkdir;
+use notes::Notes;
+pub use notes::CollectedNotes;
+use permissions::Permissions;
 use pkgs::{PkgManagersConf, Pkgs};
+pub use pkgs::{
+    install_cmd, pkg_versions, remove_pkgs, resolve_pkg_names, upgrade_pkg_managers, PkgVersions,
+    PkgsBatch, PkgsByManager, PkgsError,
+};
+use prompt::Prompt;
+use run::Run;
 use shell_script::{ShellScript, TempDirShellScript};
+use template::Template;
 
 /// Errors that can occure while performing or accessing rule's actions
 #[derive(Debug, Error)]
 pub enum RuleActionsError {
-    #[error("Index out of range")]
-    IndexOutOfRange,
+    #[error(
+        "Index {index} is out of range{}",
+        if *.len == 0 {
+            ", the rule has no actions".to_string()
+        } else {
+            format!(", valid indices are 1..{}", .len)
+        }
+    )]
+    IndexOutOfRange { index: usize, len: usize },
+
+    #[error("Range `{0}` selects no actions")]
+    EmptyRange(ActionsSelector),
+
+    #[error("Undefined action label `{0}`")]
+    UndefinedLabel(Identifier),
+
+    #[error("Action label `{0}` is used more than once")]
+    AmbiguousLabel(Identifier),
+
+    #[error("Aborted by the user")]
+    Aborted,
 
     #[error("Failed to perform `{action}` action: {err}")]
     FailedToPerform { action: String, err: Box<dyn Error> },
+
+    #[error("Failed to uninstall `{action}` action: {err}")]
+    FailedToUninstall { action: String, err: Box<dyn Error> },
 }
 
 /// Actions' configuration. Some actions can optionally use some of it's field
@@ -33,6 +101,102 @@ pub struct RuleActionsConf {
     shell: String,
     backup_dir: UserPath,
     pkg_managers: PkgManagersConf,
+
+    #[serde(default)]
+    vars: HashMap<String, serde_json::Value>,
+
+    /// Pipe scripts to the shell's standard input instead of passing them as
+    /// temporary files. Scripts can't read from the terminal in this case
+    #[serde(default)]
+    pipe_scripts: bool,
+
+    /// Environment variables exported to every script
+    #[serde(default)]
+    env: HashMap<String, String>,
+
+    /// Tools and keys used to encrypt and decrypt dotfiles
+    #[serde(default)]
+    encryption: EncryptionConf,
+
+    /// Answers to `prompt` actions given during the run
+    #[serde(skip)]
+    answers: RefCell<serde_json::Map<String, Value>>,
+
+    /// Variables of answered password prompts, that aren't exported to scripts
+    #[serde(skip)]
+    passwords: RefCell<HashSet<String>>,
+}
+
+/// Everything an action may need while being performed
+pub struct ActionContext<'a> {
+    conf: &'a RuleActionsConf,
+
+    /// A shell used by the rule. Either rule's own one or the default one
+    shell: &'a str,
+
+    rule: &'a Identifier,
+
+    /// Index of the action in the rule (counting from 1)
+    index: usize,
+
+    /// Rule's own environment variables
+    env: &'a HashMap<String, String>,
+
+    /// Packages, that are installed in batches across several rules
+    pkgs_batch: Option<&'a PkgsBatch>,
+
+    /// Notes of `notes` actions, that are printed after all the rules are
+    /// performed
+    notes: Option<&'a CollectedNotes>,
+}
+
+impl<'a> ActionContext<'a> {
+    /// Install packages of `pkgs` actions from a given batch instead of the
+    /// ones of the action itself
+    pub fn with_pkgs_batch(mut self, batch: Option<&'a PkgsBatch>) -> Self {
+        self.pkgs_batch = batch;
+        self
+    }
+
+    /// Collect notes of `notes` actions instead of printing them right away
+    pub fn with_notes(mut self, notes: Option<&'a CollectedNotes>) -> Self {
+        self.notes = notes;
+        self
+    }
+
+    /// Get environment variables, that are exported to every script run by the
+    /// action. Variables set in the configuration override the predefined ones
+    /// and rule's variables override all of them. Answers to prompts, except
+    /// passwords, are exported as `DOTM_ANSWER_<VAR>`
+    fn env(&self) -> Result<HashMap<String, String>, OSError> {
+        let opts = cli::options();
+        let dotfiles_dir = os::canonicalize(opts.dotfiles_dir())?;
+        let mut env: HashMap<_, _> = vec![
+            ("DOTM_DOTFILES_DIR", dotfiles_dir.to_string_lossy().into_owned()),
+            ("DOTM_RULE", self.rule.to_string()),
+            ("DOTM_ACTION_INDEX", self.index.to_string()),
+            ("DOTM_DISTRO", opts.distro_id()?.to_string()),
+            ("DOTM_BACKUP_DIR", self.conf.backup_dir.to_string_lossy().into_owned()),
+            ("DOTM_DRY_RUN", if opts.dry_run() { "1" } else { "0" }.to_string()),
+        ]
+        .into_iter()
+        .map(|(var, val)| (var.to_string(), val))
+        .collect();
+        env.extend(self.conf.env.clone());
+        let passwords = self.conf.passwords.borrow();
+        for (var, answer) in self.conf.answers.borrow().iter() {
+            if passwords.contains(var) {
+                continue;
+            }
+            let answer = match answer {
+                Value::String(s) => s.clone(),
+                answer => answer.to_string(),
+            };
+            env.insert(answer_env_var(var), answer);
+        }
+        env.extend(self.env.clone());
+        Ok(env)
+    }
 }
 
 /// A single action. Every rule consits of a list of such actions that are
@@ -50,17 +214,136 @@ enum RuleAction {
     /// Run shell script in a temporal directory
     InTemp(TempDirShellScript),
 
+    /// Execute a script file from the dotfiles directory
+    Run(Run),
+
     /// Create links for given files
     Links(Links),
 
+    /// Copy given files and directories
+    Copy(Copy),
+
+    /// Render given templates
+    Template(Template),
+
+    /// Ensure that a line is present in a file
+    LineInFile(LineInFile),
+
+    /// Ensure that a marked block is present in a file
+    BlockInFile(BlockInFile),
+
+    /// Create directories
+    Mkdir(Mkdir),
+
+    /// Enforce mode and owner of files
+    Permissions(Permissions),
+
+    /// Clone or update a git repository
+    Git(Git),
+
+    /// Download a file
+    Download(Download),
+
+    /// Extract an archive
+    Extract(Extract),
+
     /// State given rules as dependencies
     Deps(Deps),
+
+    /// Leave notes about manual follow-up steps
+    Notes(Notes),
+
+    /// Ask the user for a value
+    Prompt(Prompt),
+
+    /// Decrypt given files
+    Decrypt(Decrypt),
+}
+
+/// Files mapped to their destinations, e.g. links or copies
+type FilesMap = HashMap<UserPath, List<UserPath>>;
+
+/// Get a preview of files mapped to their destinations, one `source -> dest`
+/// pair per line. Pairs are reversed for links, since they point from the
+/// destinations to the sources
+fn preview_files(files: &FilesMap, reversed: bool) -> String {
+    let pairs = files.iter().flat_map(|(source, dests)| {
+        dests.iter().map(move |dest| match reversed {
+            false => format!("{} -> {}", source.display(), dest.display()),
+            true => format!("{} -> {}", dest.display(), source.display()),
+        })
+    });
+    pairs.collect::<Vec<_>>().join("\n")
+}
+
+/// State of a destination, that is prepared to be written to
+enum DestState {
+    /// There is nothing at the destination anymore
+    Ready,
+
+    /// The destination already has the contents, that would be written to it
+    UpToDate,
+
+    /// An existing file is moved to a given path in the backup directory
+    BackedUp(PathBuf),
+}
+
+/// Get the name of the environment variable, that an answer to a prompt is
+/// exported as. Characters, that can't be used in variable names, are replaced
+/// with underscores
+fn answer_env_var(var: &str) -> String {
+    let var: String = var
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect();
+    format!("DOTM_ANSWER_{}", var)
+}
+
+/// Prepare a destination to be written to. A symlink at the destination is
+/// removed, and an existing file is moved to the backup directory, unless
+/// `up_to_date` tells that it already has the right contents. With
+/// `--dry-run` nothing is changed and the backup is only reported
+fn prepare_dest(
+    dest: &Path,
+    conf: &RuleActionsConf,
+    up_to_date: impl FnOnce() -> Result<bool, Box<dyn Error>>,
+) -> Result<DestState, Box<dyn Error>> {
+    let dry_run = cli::options().dry_run();
+    if !dest.exists() {
+        return Ok(DestState::Ready);
+    }
+    if os::is_symlink(dest)? {
+        if !dry_run {
+            os::remove_file(dest)?;
+        }
+        Ok(DestState::Ready)
+    } else if up_to_date()? {
+        print_info!("`{}` is up to date", dest.display());
+        Ok(DestState::UpToDate)
+    } else if dry_run {
+        print_info!(
+            "Would move `{}` to `{}`",
+            dest.display(),
+            conf.backup_dir.display()
+        );
+        Ok(DestState::Ready)
+    } else {
+        Ok(DestState::BackedUp(os::backup(dest, &conf.backup_dir)?))
+    }
 }
 
 /// General action trait
 trait Action {
     /// Perform the action. Does nothing by default
-    fn perform(&self, _conf: &RuleActionsConf) -> Result<(), Box<dyn Error>> {
+    fn perform(&self, _ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// Undo the action, if it's possible. Does nothing by default
+    fn uninstall(&self, _ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
         Ok(())
     }
 
@@ -69,6 +352,41 @@ trait Action {
     fn get_deps(&self, _conf: &RuleActionsConf) -> HashSet<Identifier> {
         HashSet::new()
     }
+
+    /// Get a short human-readable description of what the action is going to
+    /// do. Returns `None` by default
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        None
+    }
+}
+
+/// A decision made by the user in step mode before an action is performed
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+enum Step {
+    Run,
+    Skip,
+    Abort,
+    RunRest,
+}
+
+impl Step {
+    /// Print action's preview and ask the user what to do with it
+    fn ask(action: &RuleAction, n: usize, total: usize, ctx: &ActionContext) -> Self {
+        print_info!("Action {}/{}: `{}`", n, total, action.ident());
+        if let Some(preview) = action.preview(ctx) {
+            let preview = textwrap::indent(&preview, "    ");
+            eprintln!("{}", preview.trim_end_matches('\n'));
+        }
+
+        let choices = [
+            (Step::Run, "Run"),
+            (Step::Skip, "Skip"),
+            (Step::Abort, "Abort"),
+            (Step::RunRest, "Run the rest of the rule"),
+        ];
+        let items: Vec<_> = choices.iter().map(|&(_, item)| item).collect();
+        choices[select("What to do?", &items, 0)].0
+    }
 }
 
 impl RuleAction {
@@ -81,21 +399,37 @@ impl RuleAction {
                 }
             }};
         }
-        match_dyn_action!(self; Pkgs, Shell, InTemp, Links, Deps)
+        match_dyn_action!(
+            self;
+            Pkgs, Shell, InTemp, Run, Links, Copy, Template, LineInFile, BlockInFile, Mkdir,
+            Permissions, Git, Download, Extract, Deps, Notes, Prompt, Decrypt
+        )
     }
 
     /// Wrapper around [`Action::perform`]
     ///
     /// [`Action::perform`]: self::Action::perform
-    fn perform(&self, conf: &RuleActionsConf) -> Result<(), RuleActionsError> {
+    fn perform(&self, ctx: &ActionContext) -> Result<(), RuleActionsError> {
         self.as_dyn_action()
-            .perform(conf)
+            .perform(ctx)
             .map_err(|err| RuleActionsError::FailedToPerform {
                 action: self.ident().to_owned(),
                 err,
             })
     }
 
+    /// Wrapper around [`Action::uninstall`]
+    ///
+    /// [`Action::uninstall`]: self::Action::uninstall
+    fn uninstall(&self, ctx: &ActionContext) -> Result<(), RuleActionsError> {
+        self.as_dyn_action()
+            .uninstall(ctx)
+            .map_err(|err| RuleActionsError::FailedToUninstall {
+                action: self.ident().to_owned(),
+                err,
+            })
+    }
+
     /// Wrapper aroud [`Action::get_deps`]
     ///
     /// [`Action::get_deps`]: self::Action::get_deps
@@ -103,6 +437,13 @@ impl RuleAction {
         self.as_dyn_action().get_deps(conf)
     }
 
+    /// Wrapper aroud [`Action::preview`]
+    ///
+    /// [`Action::preview`]: self::Action::preview
+    fn preview(&self, ctx: &ActionContext) -> Option<String> {
+        self.as_dyn_action().preview(ctx)
+    }
+
     /// Get the action's identifier that can be understood by the user
     fn ident(&self) -> &'static str {
         // TODO: figure ouw hot get this names derived Deserialize implementation
@@ -110,58 +451,466 @@ impl RuleAction {
             RuleAction::Pkgs(_) => "pkgs",
             RuleAction::Shell(_) => "shell",
             RuleAction::InTemp(_) => "in_temp",
+            RuleAction::Run(_) => "run",
             RuleAction::Links(_) => "links",
+            RuleAction::Copy(_) => "copy",
+            RuleAction::Template(_) => "template",
+            RuleAction::LineInFile(_) => "line_in_file",
+            RuleAction::BlockInFile(_) => "block_in_file",
+            RuleAction::Mkdir(_) => "mkdir",
+            RuleAction::Permissions(_) => "permissions",
+            RuleAction::Git(_) => "git",
+            RuleAction::Download(_) => "download",
+            RuleAction::Extract(_) => "extract",
             RuleAction::Deps(_) => "deps",
+            RuleAction::Notes(_) => "notes",
+            RuleAction::Prompt(_) => "prompt",
+            RuleAction::Decrypt(_) => "decrypt",
+        }
+    }
+}
+
+/// An action along with it's optional label, that can be used to refer to the
+/// action instead of it's index. It's a map with a single action and an
+/// optional `name` key
+#[derive(Debug)]
+struct LabeledAction {
+    name: Option<Identifier>,
+    action: RuleAction,
+}
+
+impl<'de> Deserialize<'de> for LabeledAction {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(LabeledActionVisitor)
+    }
+}
+
+struct LabeledActionVisitor;
+
+impl<'de> Visitor<'de> for LabeledActionVisitor {
+    type Value = LabeledAction;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map containing an action and an optional `name`")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut name = None;
+        let mut action: Option<(String, RuleAction)> = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "name" {
+                if name.is_some() {
+                    return Err(de::Error::duplicate_field("name"));
+                }
+                name = Some(map.next_value()?);
+            } else if let Some((kind, _)) = &action {
+                return Err(de::Error::custom(format!(
+                    "expected a single action, found both `{}` and `{}`",
+                    kind, key
+                )));
+            } else {
+                let value = map.next_value_seed(ActionSeed(&key))?;
+                action = Some((key, value));
+            }
+        }
+        match action {
+            Some((_, action)) => Ok(LabeledAction { name, action }),
+            None => Err(de::Error::custom("missing action")),
         }
     }
 }
 
+/// Deserializes [`RuleAction`] of a given kind from a value of a map entry
+struct ActionSeed<'a>(&'a str);
+
+impl<'de> DeserializeSeed<'de> for ActionSeed<'_> {
+    type Value = RuleAction;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        RuleAction::deserialize(ActionDeserializer {
+            kind: self.0,
+            value: deserializer,
+        })
+    }
+}
+
+/// Presents an action kind with a deserializer of its value as an externally
+/// tagged enum, so that [`RuleAction`] can be deserialized from them
+struct ActionDeserializer<'a, D> {
+    kind: &'a str,
+    value: D,
+}
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for ActionDeserializer<'_, D> {
+    type Error = D::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de, D: Deserializer<'de>> EnumAccess<'de> for ActionDeserializer<'_, D> {
+    type Error = D::Error;
+    type Variant = ActionValue<D>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let kind = seed.deserialize(self.kind.into_deserializer())?;
+        Ok((kind, ActionValue(self.value)))
+    }
+}
+
+/// A deserializer of an action's value
+struct ActionValue<D>(D);
+
+impl<'de, D: Deserializer<'de>> VariantAccess<'de> for ActionValue<D> {
+    type Error = D::Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Deserialize::deserialize(self.0)
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.0)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.deserialize_map(visitor)
+    }
+}
+
+/// A way of selecting actions of a rule
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum ActionsSelector {
+    /// A single action with a given index (counting from 1)
+    Index(usize),
+
+    /// An inclusive range of indices. Missing bounds stand for the first and the
+    /// last action respectively
+    Range(Option<usize>, Option<usize>),
+
+    /// A single action with a given label
+    Label(Identifier),
+}
+
+impl FromStr for ActionsSelector {
+    type Err = String;
+
+    /// Parse a selector. Valid selectors are `n`, `a..b`, `a..`, `..b` and
+    /// action labels
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_bound = |bound: &str| match bound {
+            "" => Ok(None),
+            _ => bound
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("invalid range bound: {}", bound)),
+        };
+
+        if let Ok(n) = s.parse() {
+            Ok(ActionsSelector::Index(n))
+        } else if let Some(pos) = s.find("..") {
+            let start = parse_bound(&s[..pos])?;
+            let end = parse_bound(&s[pos + 2..])?;
+            Ok(ActionsSelector::Range(start, end))
+        } else {
+            Ok(ActionsSelector::Label(s.parse()?))
+        }
+    }
+}
+
+impl fmt::Display for ActionsSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bound = |bound: &Option<usize>| bound.map(|n| n.to_string()).unwrap_or_default();
+        match self {
+            ActionsSelector::Index(n) => write!(f, "{}", n),
+            ActionsSelector::Range(start, end) => write!(f, "{}..{}", bound(start), bound(end)),
+            ActionsSelector::Label(label) => write!(f, "{}", label),
+        }
+    }
+}
+
+/// A short description of an action, that is used for listing rule's actions
+#[derive(Debug)]
+pub struct ActionInfo<'a> {
+    index: usize,
+    kind: &'static str,
+    name: Option<&'a Identifier>,
+}
+
+impl fmt::Display for ActionInfo<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}. {}", self.index, self.kind)?;
+        if let Some(name) = self.name {
+            write!(f, " ({})", name)?;
+        }
+        Ok(())
+    }
+}
+
 /// A list of actions that are parsed from a configuration file. They specify
 /// rule's dependencies and everything that have to be done when the rule is
-/// performed
+/// performed. They can be specified either as a plain list or along with
+/// rule-wide settings
 #[derive(Debug, Deserialize)]
-#[serde(transparent)]
+#[serde(from = "ShortOrFull<Vec<LabeledAction>, FullRuleActions>")]
 pub struct RuleActions {
-    actions: Vec<RuleAction>,
+    actions: Vec<LabeledAction>,
+
+    /// A shell that overrides the default one for all the actions of the rule
+    shell: Option<String>,
+
+    /// Environment variables exported to every script of the rule
+    env: HashMap<String, String>,
+}
+
+/// Actions along with rule-wide settings
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FullRuleActions {
+    #[serde(default)]
+    shell: Option<String>,
+
+    #[serde(default)]
+    env: HashMap<String, String>,
+
+    actions: Vec<LabeledAction>,
+}
+
+impl From<ShortOrFull<Vec<LabeledAction>, FullRuleActions>> for RuleActions {
+    fn from(actions: ShortOrFull<Vec<LabeledAction>, FullRuleActions>) -> Self {
+        match actions {
+            ShortOrFull::Short(actions) => RuleActions {
+                actions,
+                shell: None,
+                env: HashMap::new(),
+            },
+            ShortOrFull::Full(full) => RuleActions {
+                actions: full.actions,
+                shell: full.shell,
+                env: full.env,
+            },
+        }
+    }
 }
 
 impl RuleActions {
+    /// Create a context, that the rule's actions are performed within. Action
+    /// index is set to 0 and is updated for every performed action
+    pub fn context<'a>(
+        &'a self,
+        rule: &'a Identifier,
+        conf: &'a RuleActionsConf,
+    ) -> ActionContext<'a> {
+        ActionContext {
+            conf,
+            shell: self.shell.as_deref().unwrap_or(&conf.shell),
+            rule,
+            index: 0,
+            env: &self.env,
+            pkgs_batch: None,
+            notes: None,
+        }
+    }
+
     /// Perform all the actions in the same order as they are specified in
     /// configuration file
     ///
     /// # Errors
     /// If any of the actions fails to perform, an occurred error is returned
-    pub fn perform(&self, conf: &RuleActionsConf) -> Result<(), RuleActionsError> {
-        for action in &self.actions {
-            action.perform(conf)?;
+    pub fn perform(&self, ctx: ActionContext) -> Result<(), RuleActionsError> {
+        self.perform_indices(0..self.actions.len(), ctx)
+    }
+
+    /// Perform actions chosen by a given selector
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - selected indices are out of range (i.e. less then 1 or bigger then the
+    /// total number of action)
+    /// - there is no action with a selected label
+    /// - any of the actions fails to perform
+    pub fn perform_selected(
+        &self,
+        selector: &ActionsSelector,
+        ctx: ActionContext,
+    ) -> Result<(), RuleActionsError> {
+        let indices = self.select(selector)?;
+        self.perform_indices(indices, ctx)
+    }
+
+    /// Perform actions with given indices (counting from 0)
+    ///
+    /// In step mode the user is asked to confirm every action before it's
+    /// performed
+    fn perform_indices(
+        &self,
+        indices: impl Iterator<Item = usize>,
+        mut ctx: ActionContext,
+    ) -> Result<(), RuleActionsError> {
+        let mut step = cli::options().step();
+        for i in indices {
+            let action = &self.actions[i].action;
+            ctx.index = i + 1;
+            if step {
+                match Step::ask(action, i + 1, self.actions.len(), &ctx) {
+                    Step::Run => {}
+                    Step::Skip => continue,
+                    Step::Abort => return Err(RuleActionsError::Aborted),
+                    Step::RunRest => step = false,
+                }
+            }
+            action.perform(&ctx)?;
         }
         Ok(())
     }
 
-    /// Perform nth action (indexing from one)
+    /// Undo all the actions in reverse order
     ///
     /// # Errors
-    /// Returns an error if:
-    /// - index is out of range (i.e. less then 1 or bigger then the total
-    /// number of action)
-    /// - the action fails to perform
-    pub fn perform_nth(&self, n: usize, conf: &RuleActionsConf) -> Result<(), RuleActionsError> {
-        if n == 0 {
-            return Err(RuleActionsError::IndexOutOfRange);
+    /// If any of the actions fails to uninstall, an occurred error is returned
+    pub fn uninstall(&self, mut ctx: ActionContext) -> Result<(), RuleActionsError> {
+        for (i, action) in self.actions.iter().enumerate().rev() {
+            ctx.index = i + 1;
+            action.action.uninstall(&ctx)?;
         }
-        self.actions
-            .get(n - 1)
-            .ok_or(RuleActionsError::IndexOutOfRange)?
-            .perform(conf)?;
         Ok(())
     }
 
+    /// Add packages of all the `pkgs` actions to a given collection
+    pub fn add_pkgs_to(&self, collection: &mut PkgsByManager) {
+        for action in &self.actions {
+            if let RuleAction::Pkgs(pkgs) = &action.action {
+                pkgs.add_to(collection);
+            }
+        }
+    }
+
+    /// Get a range of indices (counting from 0) of the actions chosen by a given
+    /// selector
+    fn select(&self, selector: &ActionsSelector) -> Result<Range<usize>, RuleActionsError> {
+        let len = self.actions.len();
+        let check_index = |index: usize| {
+            if index == 0 || index > len {
+                Err(RuleActionsError::IndexOutOfRange { index, len })
+            } else {
+                Ok(index)
+            }
+        };
+
+        match selector {
+            ActionsSelector::Index(n) => Ok(check_index(*n)? - 1..*n),
+            ActionsSelector::Range(None, None) => Ok(0..len),
+            ActionsSelector::Range(start, end) => {
+                let start = check_index(start.unwrap_or(1))?;
+                let end = check_index(end.unwrap_or(len))?;
+                if start > end {
+                    Err(RuleActionsError::EmptyRange(selector.clone()))?;
+                }
+                Ok(start - 1..end)
+            }
+            ActionsSelector::Label(label) => {
+                let mut found = self.actions.iter().positions(|a| a.name.as_ref() == Some(label));
+                match (found.next(), found.next()) {
+                    (Some(i), None) => Ok(i..i + 1),
+                    (Some(_), Some(_)) => Err(RuleActionsError::AmbiguousLabel(label.clone())),
+                    (None, _) => Err(RuleActionsError::UndefinedLabel(label.clone())),
+                }
+            }
+        }
+    }
+
+    /// Get short descriptions of all the actions in the same order as they are
+    /// specified in configuration file
+    pub fn list(&self) -> impl Iterator<Item = ActionInfo<'_>> {
+        self.actions.iter().zip(1..).map(|(a, index)| ActionInfo {
+            index,
+            kind: a.action.ident(),
+            name: a.name.as_ref(),
+        })
+    }
+
     /// Collect dependencies of all the separate actions
     pub fn get_deps(&self, conf: &RuleActionsConf) -> HashSet<Identifier> {
         self.actions
             .iter()
-            .map(|a| a.get_deps(conf))
+            .map(|a| a.action.get_deps(conf))
             .flatten()
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ActionsSelector;
+
+    fn parse(s: &str) -> Result<ActionsSelector, String> {
+        s.parse()
+    }
+
+    #[test]
+    fn index() {
+        assert_eq!(parse("3"), Ok(ActionsSelector::Index(3)));
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse("2..4"), Ok(ActionsSelector::Range(Some(2), Some(4))));
+        assert_eq!(parse("2.."), Ok(ActionsSelector::Range(Some(2), None)));
+        assert_eq!(parse("..4"), Ok(ActionsSelector::Range(None, Some(4))));
+        assert_eq!(parse(".."), Ok(ActionsSelector::Range(None, None)));
+    }
+
+    #[test]
+    fn invalid_range_bounds() {
+        assert!(parse("a..4").is_err());
+        assert!(parse("2..b").is_err());
+        assert!(parse("-1..").is_err());
+    }
+
+    #[test]
+    fn label() {
+        let label = "install-plugins".parse().unwrap();
+        assert_eq!(parse("install-plugins"), Ok(ActionsSelector::Label(label)));
+        assert!(parse("two words").is_err());
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for s in &["3", "2..4", "2..", "..4", "..", "label"] {
+            assert_eq!(parse(s).unwrap().to_string(), *s);
+        }
+    }
+}
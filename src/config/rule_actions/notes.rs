@@ -0,0 +1,53 @@
+//! An action that leaves notes about manual follow-up steps
+
+use super::{Action, ActionContext};
+use crate::config::deserializers::List;
+use crate::types::Identifier;
+use std::cell::RefCell;
+use std::error::Error;
+
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+pub struct Notes {
+    notes: List<String>,
+}
+
+/// Notes left by performed rules, that are printed once all of them are
+/// performed
+#[derive(Debug, Default)]
+pub struct CollectedNotes {
+    notes: RefCell<Vec<(Identifier, String)>>,
+}
+
+impl CollectedNotes {
+    /// Print all the collected notes along with rules that left them
+    pub fn print(&self) {
+        let notes = self.notes.borrow();
+        if notes.is_empty() {
+            return;
+        }
+        print_info!("Notes:");
+        for (rule, note) in notes.iter() {
+            print_info!("`{}`: {}", rule, note);
+        }
+    }
+}
+
+impl Action for Notes {
+    fn perform(&self, ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        for note in self.notes.iter() {
+            match ctx.notes {
+                Some(collected) => {
+                    let note = (ctx.rule.clone(), note.clone());
+                    collected.notes.borrow_mut().push(note);
+                }
+                None => print_info!("Note: {}", note),
+            }
+        }
+        Ok(())
+    }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        Some(self.notes.join("\n"))
+    }
+}
@@ -0,0 +1,144 @@
+//! An action that enforces mode and owner of files
+
+use super::{Action, ActionContext};
+use crate::cli;
+use crate::config::deserializers::List;
+use crate::os;
+use crate::types::{Mode, UserPath};
+use std::env::set_current_dir;
+use std::error::Error;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Permissions {
+    /// Paths or glob patterns
+    paths: List<UserPath>,
+
+    #[serde(default)]
+    mode: Option<Mode>,
+
+    /// Either `user`, `user:group` or `:group`
+    #[serde(default)]
+    owner: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum PermissionsError {
+    #[error("Undefined user `{0}`")]
+    UndefinedUser(String),
+
+    #[error("Undefined group `{0}`")]
+    UndefinedGroup(String),
+
+    #[error("Invalid glob pattern `{0}`")]
+    InvalidPattern(String),
+}
+
+impl Permissions {
+    /// Get user and group ids specified by `owner` field
+    fn owner_ids(&self) -> Result<(Option<u32>, Option<u32>), PermissionsError> {
+        let owner = match &self.owner {
+            Some(owner) => owner,
+            None => return Ok((None, None)),
+        };
+        let (user, group) = match owner.find(':') {
+            Some(pos) => (&owner[..pos], Some(&owner[pos + 1..])),
+            None => (&owner[..], None),
+        };
+
+        let uid = match user {
+            "" => None,
+            _ => Some(
+                users::get_user_by_name(user)
+                    .ok_or_else(|| PermissionsError::UndefinedUser(user.to_string()))?
+                    .uid(),
+            ),
+        };
+        let gid = match group {
+            None | Some("") => None,
+            Some(group) => Some(
+                users::get_group_by_name(group)
+                    .ok_or_else(|| PermissionsError::UndefinedGroup(group.to_string()))?
+                    .gid(),
+            ),
+        };
+        Ok((uid, gid))
+    }
+
+    /// Expand glob patterns of `paths` field
+    fn expand_paths(&self) -> Result<Vec<glob::GlobResult>, PermissionsError> {
+        let mut paths = Vec::new();
+        for pattern in self.paths.iter() {
+            let pattern = pattern.to_string_lossy();
+            let matches: Vec<_> = glob::glob(&pattern)
+                .map_err(|_| PermissionsError::InvalidPattern(pattern.to_string()))?
+                .collect();
+            if matches.is_empty() {
+                print_warn!("No files match `{}`", pattern);
+            }
+            paths.extend(matches);
+        }
+        Ok(paths)
+    }
+
+    /// Enforce permissions on a single file
+    fn enforce(
+        &self,
+        path: &Path,
+        uid: Option<u32>,
+        gid: Option<u32>,
+        dry_run: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let metadata = os::metadata(path)?;
+        let old_mode = Mode::from(metadata.mode());
+        if let Some(mode) = self.mode.filter(|&mode| mode != old_mode) {
+            print_info!("`{}`: mode {} -> {}", path.display(), old_mode, mode);
+            if !dry_run {
+                os::set_mode(path, mode)?;
+            }
+        }
+
+        let uid = uid.filter(|&uid| uid != metadata.uid());
+        let gid = gid.filter(|&gid| gid != metadata.gid());
+        if uid.is_some() || gid.is_some() {
+            print_info!(
+                "`{}`: owner {}:{} -> {}:{}",
+                path.display(),
+                metadata.uid(),
+                metadata.gid(),
+                uid.unwrap_or_else(|| metadata.uid()),
+                gid.unwrap_or_else(|| metadata.gid())
+            );
+            if !dry_run {
+                os::chown(path, uid, gid)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Action for Permissions {
+    fn perform(&self, _ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        set_current_dir(cli::options().dotfiles_dir())?;
+        let (uid, gid) = self.owner_ids()?;
+        for path in self.expand_paths()? {
+            self.enforce(&path?, uid, gid, cli::options().dry_run())?;
+        }
+        Ok(())
+    }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        let mut preview = Vec::new();
+        if let Some(mode) = self.mode {
+            preview.push(format!("mode: {}", mode));
+        }
+        if let Some(owner) = &self.owner {
+            preview.push(format!("owner: {}", owner));
+        }
+        preview.extend(self.paths.iter().map(|path| path.display().to_string()));
+        Some(preview.join("\n"))
+    }
+}
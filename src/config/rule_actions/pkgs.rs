@@ -1,67 +1,397 @@
 //! An action that installes packages using preconfigured package managers
 
-use super::{Action, RuleActionsConf};
+use super::{Action, ActionContext, RuleActionsConf};
 use crate::cli;
 use crate::config::deserializers::List;
+use crate::os::{self, OSError, Script};
 use crate::types::{Identifier, Identifiers};
-use crate::os::run_shell_script;
 use once_cell::sync::Lazy;
 use regex::{Captures, Regex};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use itertools::Itertools;
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::iter;
+use std::process::Command;
+use std::slice;
+use std::time::Duration;
 use thiserror::Error;
 
-#[derive(Debug, Deserialize)]
-#[serde(transparent)]
+/// Packages to install, grouped by package managers. They're given either
+/// directly or under `pkgs` key along with the installation settings
+#[derive(Debug)]
 pub struct Pkgs {
     pkgs: HashMap<Identifier, List<String>>,
+
+    /// Timeout of a single installation command in seconds
+    timeout: Option<u64>,
+
+    /// Number of times a failed installation command is run again
+    retries: u32,
+}
+
+/// Fields of the full form of `Pkgs`
+const PKGS_FIELDS: &[&str] = &["pkgs", "timeout", "retries"];
+
+impl<'de> Deserialize<'de> for Pkgs {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(PkgsVisitor)
+    }
+}
+
+/// Tells the forms of `Pkgs` apart by their keys, so that errors of the full
+/// form are reported as they are
+struct PkgsVisitor;
+
+impl<'de> Visitor<'de> for PkgsVisitor {
+    type Value = Pkgs;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of package managers to packages")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut pkgs = None;
+        let mut timeout = None;
+        let mut retries = None;
+        let mut short = HashMap::<Identifier, List<String>>::new();
+        let mut full = false;
+        while let Some(key) = map.next_key::<Identifier>()? {
+            let is_field = PKGS_FIELDS.contains(&&*key);
+            match short.keys().next() {
+                Some(mngr) if is_field => return Err(de::Error::unknown_field(mngr, PKGS_FIELDS)),
+                _ if full && !is_field => return Err(de::Error::unknown_field(&key, PKGS_FIELDS)),
+                _ => full |= is_field,
+            }
+            match &*key {
+                "pkgs" => pkgs = Some(map.next_value()?),
+                "timeout" => timeout = map.next_value()?,
+                "retries" => retries = Some(map.next_value()?),
+                _ => drop(short.insert(key, map.next_value()?)),
+            }
+        }
+
+        match pkgs {
+            Some(pkgs) => Ok(Pkgs {
+                pkgs,
+                timeout,
+                retries: retries.unwrap_or(0),
+            }),
+            None if full => Err(de::Error::missing_field("pkgs")),
+            None => Ok(Pkgs {
+                pkgs: short,
+                timeout: None,
+                retries: 0,
+            }),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct PkgManagersConf {
     install_cmds: HashMap<Identifier, String>,
     deps: HashMap<Identifier, Identifiers>,
+
+    /// Commands checking if a package is installed. They should succeed for
+    /// installed packages and fail otherwise
+    #[serde(default)]
+    check_cmds: HashMap<Identifier, String>,
+
+    #[serde(default)]
+    remove_cmds: HashMap<Identifier, String>,
+
+    /// Commands upgrading all the packages installed by a package manager
+    #[serde(default)]
+    upgrade_cmds: HashMap<Identifier, String>,
+
+    /// Package names used by particular package managers or distributions,
+    /// e.g. `fd: {debian: fd-find}`
+    #[serde(default)]
+    aliases: HashMap<String, HashMap<String, String>>,
+
+    /// Package managers that are tried in order if a package fails to install
+    #[serde(default)]
+    fallbacks: HashMap<String, Vec<Identifier>>,
+
+    /// Commands checking if a package manager is available. Dependencies of an
+    /// available package manager aren't installed
+    #[serde(default)]
+    detect_cmds: HashMap<Identifier, String>,
+
+    /// Commands printing the installed version of a package
+    #[serde(default)]
+    version_cmds: HashMap<Identifier, String>,
+
+    /// Cached results of detect commands
+    #[serde(skip)]
+    detected: RefCell<HashMap<Identifier, bool>>,
+}
+
+/// Packages grouped by package managers
+pub type PkgsByManager = BTreeMap<Identifier, BTreeSet<String>>;
+
+/// Versions of packages grouped by package managers
+pub type PkgVersions = BTreeMap<Identifier, BTreeMap<String, String>>;
+
+/// Packages of several rules grouped by package managers, that are installed
+/// at once when the first of the rules is performed
+#[derive(Debug, Default)]
+pub struct PkgsBatch {
+    pending: RefCell<PkgsByManager>,
+}
+
+impl From<PkgsByManager> for PkgsBatch {
+    fn from(pkgs: PkgsByManager) -> Self {
+        PkgsBatch {
+            pending: RefCell::new(pkgs),
+        }
+    }
+}
+
+impl PkgsBatch {
+    /// Take all the pending packages of a given package manager. Returns
+    /// `None` if they have already been taken
+    fn take(&self, mngr: &Identifier) -> Option<Vec<String>> {
+        let pkgs = self.pending.borrow_mut().remove(mngr)?;
+        Some(pkgs.into_iter().collect())
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum PkgsError {
     #[error("Undefined package manager `{0}`")]
     UndefinedPkgMngr(Identifier),
+
+    #[error(
+        "Package manager `{mngr}` is not available, `{cmd}` fails{}",
+        if .deps.is_empty() {
+            String::new()
+        } else {
+            let mut deps = .deps.iter().map(|dep| format!("`{}`", dep));
+            format!(" even after installing {}", deps.join(", "))
+        }
+    )]
+    UnavailablePkgMngr {
+        mngr: Identifier,
+        cmd: String,
+        deps: Vec<Identifier>,
+    },
+
+    #[error(transparent)]
+    OSError(#[from] OSError),
 }
 
+static PKG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"%(%|pkgs|pkg)").unwrap());
+
 impl Pkgs {
-    fn substitude_pkg<'a>(s: &'a str, pkg: &str) -> Cow<'a, str> {
-        static PKG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"%(%|pkg)").unwrap());
+    /// Add packages of the action to a given collection
+    pub fn add_to(&self, collection: &mut PkgsByManager) {
+        for (mngr, pkgs) in &self.pkgs {
+            let collected = collection.entry(mngr.clone()).or_default();
+            collected.extend(pkgs.iter().cloned());
+        }
+    }
+
+    /// Substitute packages into an install command. `%pkgs` is replaced with
+    /// shell-quoted packages, while `%pkg` is replaced with them as is
+    fn substitude_pkgs<'a>(s: &'a str, pkgs: &[String]) -> Cow<'a, str> {
         PKG_RE.replace_all(s, |caps: &Captures| match &caps[1] {
-            "%" => "%",
-            "pkg" => pkg,
+            "%" => "%".to_string(),
+            "pkgs" => pkgs.iter().map(|pkg| shell_quote(pkg)).join(" "),
+            "pkg" => pkgs.join(" "),
             _ => unreachable!(),
         })
     }
+
+    /// Get install or remove commands for given packages. If the command uses
+    /// `%pkgs`, all the packages are processed at once. Otherwise, they are
+    /// processed one by one
+    fn pkg_cmds<'a>(cmd: &'a str, pkgs: &[String]) -> Vec<Cow<'a, str>> {
+        let batched = PKG_RE.captures_iter(cmd).any(|caps| &caps[1] == "pkgs");
+        if batched {
+            vec![Self::substitude_pkgs(cmd, pkgs)]
+        } else {
+            pkgs.iter()
+                .map(|pkg| Self::substitude_pkgs(cmd, slice::from_ref(pkg)))
+                .collect()
+        }
+    }
+
+    /// Filter out packages, that are already installed, using package
+    /// manager's check command. If there is no such command, all the packages
+    /// are considered not installed
+    fn not_installed(
+        ctx: &ActionContext,
+        mngr: &Identifier,
+        pkgs: Vec<String>,
+        env: &HashMap<String, String>,
+    ) -> Result<Vec<String>, OSError> {
+        let check_cmd = match ctx.conf.pkg_managers.check_cmds.get(mngr) {
+            Some(check_cmd) => check_cmd,
+            None => return Ok(pkgs),
+        };
+
+        let mut missing = Vec::new();
+        for pkg in &pkgs {
+            let name = ctx.conf.pkg_managers.resolve_name(mngr, pkg)?.to_string();
+            let check = Self::substitude_pkgs(check_cmd, &[name]);
+            let mut cmd = Command::new(&ctx.conf.shell);
+            cmd.arg("-c")
+                .arg(&*check)
+                .envs(env)
+                .current_dir(cli::options().dotfiles_dir());
+            if !os::command_succeeds(&mut cmd)? {
+                missing.push(pkg.clone());
+            }
+        }
+        print_info!(
+            "`{}`: {} already installed, {} to install",
+            mngr,
+            pkgs.len() - missing.len(),
+            missing.len()
+        );
+        Ok(missing)
+    }
+
+    /// Install packages using a given package manager
+    fn install(
+        &self,
+        ctx: &ActionContext,
+        mngr: &Identifier,
+        pkgs: &[String],
+        env: &HashMap<String, String>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mngr_cmd = ctx.conf.pkg_managers.get_cmd(mngr)?;
+        let pkgs = ctx.conf.pkg_managers.resolve_names(mngr, pkgs)?;
+        if cli::options().dry_run() {
+            for cmd in Self::pkg_cmds(mngr_cmd, &pkgs) {
+                print_info!("Would run `{}`", cmd);
+            }
+            return Ok(());
+        }
+        ctx.conf.pkg_managers.ensure_available(mngr, &ctx.conf.shell, env)?;
+        for cmd in Self::pkg_cmds(mngr_cmd, &pkgs) {
+            Script::new(&ctx.conf.shell, &cmd)
+                .envs(env)
+                .piped(ctx.conf.pipe_scripts)
+                .timeout(self.timeout.map(Duration::from_secs))
+                .retries(self.retries)
+                .run(cli::options().dotfiles_dir())?;
+        }
+        Ok(())
+    }
+
+    /// Install a package using a given package manager. If it fails, package
+    /// managers from the package's fallbacks list are tried in order
+    fn install_with_fallbacks(
+        &self,
+        ctx: &ActionContext,
+        mngr: &Identifier,
+        pkg: &String,
+        env: &HashMap<String, String>,
+    ) -> Result<(), Box<dyn Error>> {
+        let fallbacks = ctx.conf.pkg_managers.fallbacks[pkg].iter();
+        let mut mngrs = iter::once(mngr)
+            .chain(fallbacks.filter(|&fallback| fallback != mngr))
+            .peekable();
+        while let Some(mngr) = mngrs.next() {
+            match (self.install(ctx, mngr, slice::from_ref(pkg), env), mngrs.peek()) {
+                (Err(err), Some(next)) => print_warn!(
+                    "Failed to install `{}` with `{}`: {}. Trying `{}`",
+                    pkg,
+                    mngr,
+                    err,
+                    next
+                ),
+                (res, _) => return res,
+            }
+        }
+        unreachable!()
+    }
+}
+
+/// Quote a string, so that it's interpreted by a shell as a single word
+fn shell_quote(s: &str) -> Cow<'_, str> {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./:+=@,%".contains(c);
+    if !s.is_empty() && s.chars().all(is_safe) {
+        s.into()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''")).into()
+    }
 }
 
 impl Action for Pkgs {
-    fn perform(&self, conf: &RuleActionsConf) -> Result<(), Box<dyn std::error::Error>> {
+    fn perform(&self, ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
         for pkg_mngr in self.pkgs.keys() {
-            conf.pkg_managers.get_cmd(pkg_mngr)?;
+            ctx.conf.pkg_managers.get_cmd(pkg_mngr)?;
         }
+        let env = ctx.env()?;
         for (pkg_mngr, pkgs) in &self.pkgs {
-            let pkg_mngr_cmd = conf.pkg_managers.get_cmd(pkg_mngr).unwrap();
-            for pkg in pkgs.iter() {
-                run_shell_script(
-                    &conf.shell,
-                    cli::options().dotfiles_dir(),
-                    &Self::substitude_pkg(pkg_mngr_cmd, pkg),
-                )?;
+            let pkgs = match ctx.pkgs_batch {
+                None => pkgs.to_vec(),
+                Some(batch) => match batch.take(pkg_mngr) {
+                    Some(pkgs) => pkgs,
+                    None => {
+                        print_info!("`{}` packages are already installed in a batch", pkg_mngr);
+                        continue;
+                    }
+                },
+            };
+            let pkgs = Self::not_installed(ctx, pkg_mngr, pkgs, &env)?;
+            let (with_fallbacks, pkgs): (Vec<_>, Vec<_>) = pkgs
+                .into_iter()
+                .partition(|pkg| ctx.conf.pkg_managers.fallbacks.contains_key(pkg));
+            if !pkgs.is_empty() {
+                self.install(ctx, pkg_mngr, &pkgs, &env)?;
+            }
+            for pkg in &with_fallbacks {
+                self.install_with_fallbacks(ctx, pkg_mngr, pkg, &env)?;
             }
         }
         Ok(())
     }
 
     fn get_deps(&self, conf: &RuleActionsConf) -> HashSet<Identifier> {
-        let deps = self.pkgs.keys().map(|mgr| conf.pkg_managers.get_deps(mgr));
+        let pkg_mngrs = &conf.pkg_managers;
+        // Package managers, that packages may fall back to, have to be
+        // available as well
+        let fallbacks = self
+            .pkgs
+            .values()
+            .flat_map(|pkgs| pkgs.iter())
+            .filter_map(|pkg| pkg_mngrs.fallbacks.get(pkg))
+            .flatten();
+        let mngrs: HashSet<_> = self.pkgs.keys().chain(fallbacks).collect();
+        let deps = mngrs.into_iter().filter_map(|mngr| match pkg_mngrs.is_detected(mngr, conf) {
+            true => None,
+            false => Some(pkg_mngrs.get_deps(mngr)),
+        });
         deps.flatten().collect()
     }
+
+    fn preview(&self, ctx: &ActionContext) -> Option<String> {
+        let mut cmds = Vec::new();
+        for (pkg_mngr, pkgs) in &self.pkgs {
+            let pkg_mngrs = &ctx.conf.pkg_managers;
+            match pkg_mngrs.get_cmd(pkg_mngr) {
+                Ok(cmd) => match pkg_mngrs.resolve_names(pkg_mngr, pkgs) {
+                    Ok(pkgs) => cmds.extend(Self::pkg_cmds(cmd, &pkgs)),
+                    Err(err) => cmds.push(err.to_string().into()),
+                },
+                Err(err) => cmds.push(err.to_string().into()),
+            }
+        }
+        Some(cmds.join("\n"))
+    }
 }
 
 impl PkgManagersConf {
@@ -73,4 +403,268 @@ impl PkgManagersConf {
     fn get_deps(&self, mngr: &Identifier) -> impl Iterator<Item = Identifier> + '_ {
         self.deps.get(mngr).into_iter().flatten()
     }
+
+    /// Run package manager's detect command. Returns `None` if there is no
+    /// such command
+    ///
+    /// # Errors
+    /// Returns an error if the shell fails to start
+    fn detect(
+        &self,
+        mngr: &Identifier,
+        shell: &str,
+        env: &HashMap<String, String>,
+    ) -> Result<Option<bool>, OSError> {
+        let detect_cmd = match self.detect_cmds.get(mngr) {
+            Some(detect_cmd) => detect_cmd,
+            None => return Ok(None),
+        };
+        let mut cmd = Command::new(shell);
+        cmd.arg("-c")
+            .arg(detect_cmd)
+            .envs(env)
+            .current_dir(cli::options().dotfiles_dir());
+        Ok(Some(os::command_succeeds(&mut cmd)?))
+    }
+
+    /// Check if a package manager is detected to be available. Package
+    /// managers without detect commands are never detected, so that their
+    /// dependencies are always installed. Results are cached
+    fn is_detected(&self, mngr: &Identifier, conf: &RuleActionsConf) -> bool {
+        if let Some(&detected) = self.detected.borrow().get(mngr) {
+            return detected;
+        }
+        // If the detect command can't be run, dependencies are installed and
+        // the error is reported once the package manager is used
+        let detected = matches!(self.detect(mngr, &conf.shell, &conf.env), Ok(Some(true)));
+        self.detected.borrow_mut().insert(mngr.clone(), detected);
+        detected
+    }
+
+    /// Ensure that a package manager is available right before it's used
+    fn ensure_available(
+        &self,
+        mngr: &Identifier,
+        shell: &str,
+        env: &HashMap<String, String>,
+    ) -> Result<(), PkgsError> {
+        match self.detect(mngr, shell, env)? {
+            Some(false) => Err(PkgsError::UnavailablePkgMngr {
+                mngr: mngr.clone(),
+                cmd: self.detect_cmds[mngr].clone(),
+                deps: self.get_deps(mngr).collect(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Get the name of a package used by a given package manager. Aliases are
+    /// looked up by the package manager first and then by the current
+    /// distribution id. Packages without aliases keep their names
+    fn resolve_name<'a>(&'a self, mngr: &Identifier, pkg: &'a str) -> Result<&'a str, OSError> {
+        let aliases = match self.aliases.get(pkg) {
+            Some(aliases) => aliases,
+            None => return Ok(pkg),
+        };
+        if let Some(name) = aliases.get(&**mngr) {
+            return Ok(name);
+        }
+        let distro_id = cli::options().distro_id()?;
+        Ok(aliases.get(distro_id).map_or(pkg, String::as_str))
+    }
+
+    /// Resolve names of several packages. See [`resolve_name`]
+    ///
+    /// [`resolve_name`]: PkgManagersConf::resolve_name
+    fn resolve_names(&self, mngr: &Identifier, pkgs: &[String]) -> Result<Vec<String>, OSError> {
+        pkgs.iter()
+            .map(|pkg| Ok(self.resolve_name(mngr, pkg)?.to_string()))
+            .collect()
+    }
+}
+
+/// Remove given packages using remove commands of their package managers.
+/// Packages of managers that have no remove command are kept
+pub fn remove_pkgs(conf: &RuleActionsConf, pkgs: &PkgsByManager) -> Result<(), OSError> {
+    for (mngr, pkgs) in pkgs {
+        if pkgs.is_empty() {
+            continue;
+        }
+        let remove_cmd = match conf.pkg_managers.remove_cmds.get(mngr) {
+            Some(remove_cmd) => remove_cmd,
+            None => {
+                print_warn!("`{}` has no remove command, its packages are kept", mngr);
+                continue;
+            }
+        };
+        let pkgs: Vec<_> = pkgs.iter().cloned().collect();
+        let pkgs = conf.pkg_managers.resolve_names(mngr, &pkgs)?;
+        for cmd in Pkgs::pkg_cmds(remove_cmd, &pkgs) {
+            run_conf_cmd(conf, &cmd)?;
+        }
+    }
+    Ok(())
+}
+
+/// Resolve names of given packages for their package managers. See
+/// [`PkgManagersConf::resolve_name`]
+pub fn resolve_pkg_names(
+    conf: &RuleActionsConf,
+    pkgs: &PkgsByManager,
+) -> Result<PkgsByManager, OSError> {
+    let mut resolved = PkgsByManager::new();
+    for (mngr, pkgs) in pkgs {
+        let pkgs: Vec<_> = pkgs.iter().cloned().collect();
+        let names = conf.pkg_managers.resolve_names(mngr, &pkgs)?;
+        resolved.insert(mngr.clone(), names.into_iter().collect());
+    }
+    Ok(resolved)
+}
+
+/// Get a single command, that installs all the given packages of a package
+/// manager
+pub fn install_cmd(
+    conf: &RuleActionsConf,
+    mngr: &Identifier,
+    pkgs: &[String],
+) -> Result<String, PkgsError> {
+    let cmd = conf.pkg_managers.get_cmd(mngr)?;
+    Ok(Pkgs::substitude_pkgs(cmd, pkgs).into_owned())
+}
+
+/// Get installed versions of given packages using package managers' version
+/// commands. Packages that aren't installed and packages of managers without
+/// version commands are omitted
+pub fn pkg_versions(
+    conf: &RuleActionsConf,
+    pkgs: &PkgsByManager,
+) -> Result<PkgVersions, OSError> {
+    let mut versions = PkgVersions::new();
+    for (mngr, pkgs) in pkgs {
+        let version_cmd = match conf.pkg_managers.version_cmds.get(mngr) {
+            Some(version_cmd) => version_cmd,
+            None => {
+                print_warn!("`{}` has no version command", mngr);
+                continue;
+            }
+        };
+        let mngr_versions = versions.entry(mngr.clone()).or_default();
+        for pkg in pkgs {
+            let mut cmd = Command::new(&conf.shell);
+            cmd.arg("-c")
+                .arg(&*Pkgs::substitude_pkgs(version_cmd, slice::from_ref(pkg)))
+                .envs(&conf.env)
+                .current_dir(cli::options().dotfiles_dir());
+            match os::quiet_output(&mut cmd)? {
+                Some(version) => {
+                    mngr_versions.insert(pkg.clone(), version.trim().to_string());
+                }
+                None => print_warn!("Failed to get version of `{}`, is it installed?", pkg),
+            }
+        }
+    }
+    Ok(versions)
+}
+
+/// Run upgrade commands of given package managers
+pub fn upgrade_pkg_managers<'a>(
+    conf: &RuleActionsConf,
+    mngrs: impl Iterator<Item = &'a Identifier>,
+) -> Result<(), OSError> {
+    for mngr in mngrs {
+        match conf.pkg_managers.upgrade_cmds.get(mngr) {
+            Some(cmd) => {
+                print_info!("Upgrading `{}` packages...", mngr);
+                run_conf_cmd(conf, cmd)?;
+            }
+            None => print_warn!("`{}` has no upgrade command", mngr),
+        }
+    }
+    Ok(())
+}
+
+/// Run a command, that isn't a part of any rule, with the default shell. With
+/// `--dry-run` the command is only printed
+fn run_conf_cmd(conf: &RuleActionsConf, cmd: &str) -> Result<(), OSError> {
+    if cli::options().dry_run() {
+        print_info!("Would run `{}`", cmd);
+        return Ok(());
+    }
+    Script::new(&conf.shell, cmd)
+        .envs(&conf.env)
+        .piped(conf.pipe_scripts)
+        .run(cli::options().dotfiles_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{shell_quote, Pkgs};
+
+    fn pkgs(pkgs: &[&str]) -> Vec<String> {
+        pkgs.iter().map(|pkg| pkg.to_string()).collect()
+    }
+
+    fn parse(yaml: &str) -> Result<Pkgs, String> {
+        serde_yaml::from_str(yaml).map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn short_form() {
+        let pkgs = parse("{pacman: [a, b], pip: c}").unwrap();
+        assert_eq!(pkgs.pkgs.len(), 2);
+        assert_eq!(pkgs.timeout, None);
+    }
+
+    #[test]
+    fn full_form() {
+        let pkgs = parse("{pkgs: {pacman: [a]}, timeout: 60, retries: 2}").unwrap();
+        assert_eq!(pkgs.pkgs.len(), 1);
+        assert_eq!((pkgs.timeout, pkgs.retries), (Some(60), 2));
+    }
+
+    #[test]
+    fn full_form_errors() {
+        let err = parse("{pkgs: {echo: [a]}, timeout: x}").unwrap_err();
+        assert!(err.contains("expected u64"), "{}", err);
+        let err = parse("{pkgs: {echo: [a]}, timout: 60}").unwrap_err();
+        assert!(err.contains("unknown field `timout`"), "{}", err);
+        let err = parse("{echo: [a], retries: 2}").unwrap_err();
+        assert!(err.contains("unknown field `echo`"), "{}", err);
+        let err = parse("{retries: 2}").unwrap_err();
+        assert!(err.contains("missing field `pkgs`"), "{}", err);
+    }
+
+    #[test]
+    fn safe_words_are_not_quoted() {
+        assert_eq!(shell_quote("python3-pip"), "python3-pip");
+        assert_eq!(shell_quote("github.com/user/repo@v1.0"), "github.com/user/repo@v1.0");
+    }
+
+    #[test]
+    fn unsafe_words_are_quoted() {
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("$(rm -rf ~)"), "'$(rm -rf ~)'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn batched_cmd() {
+        let cmds = Pkgs::pkg_cmds("pacman -S %pkgs", &pkgs(&["a", "b c"]));
+        assert_eq!(cmds, vec!["pacman -S a 'b c'"]);
+    }
+
+    #[test]
+    fn cmd_per_pkg() {
+        let cmds = Pkgs::pkg_cmds("pip install %pkg", &pkgs(&["a", "b"]));
+        assert_eq!(cmds, vec!["pip install a", "pip install b"]);
+    }
+
+    #[test]
+    fn escaped_percent() {
+        let cmds = Pkgs::pkg_cmds("echo 100%% %pkg", &pkgs(&["a"]));
+        assert_eq!(cmds, vec!["echo 100% a"]);
+        let cmds = Pkgs::pkg_cmds("echo %%pkgs %pkg", &pkgs(&["a", "b"]));
+        assert_eq!(cmds, vec!["echo %pkgs a", "echo %pkgs b"]);
+    }
 }
@@ -0,0 +1,189 @@
+//! An action that asks the user for a value, which is available to the later
+//! actions of the run
+
+use super::{Action, ActionContext};
+use crate::cli;
+use crate::io;
+use crate::os::{self, OSError};
+use serde_json::{Map, Value};
+use std::error::Error;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Prompt {
+    /// Name of the variable, that stores the answer
+    var: String,
+    message: String,
+
+    #[serde(default)]
+    kind: PromptKind,
+
+    /// Items to choose from, if `kind` is `select`
+    #[serde(default)]
+    choices: Vec<String>,
+
+    /// An answer, that is used by default and with `--noconfirm`
+    #[serde(default)]
+    default: Option<Value>,
+
+    /// Save the answer to the answers file, so that it's reused by later runs
+    /// instead of asking again
+    #[serde(default)]
+    save: bool,
+}
+
+#[derive(Debug, Default, Copy, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PromptKind {
+    #[default]
+    Text,
+    Password,
+    Select,
+    Confirm,
+}
+
+#[derive(Debug, Error)]
+pub enum PromptError {
+    #[error("`{0}` has no default answer, so it can't be answered with `--noconfirm`")]
+    NoAnswer(String),
+
+    #[error("Invalid default answer of `{0}`")]
+    InvalidDefault(String),
+
+    #[error("`{0}` has nothing to choose from")]
+    NoChoices(String),
+
+    #[error("Invalid answers file `{path}`: {err}")]
+    InvalidAnswers {
+        path: PathBuf,
+        #[source]
+        err: serde_json::Error,
+    },
+
+    #[error(transparent)]
+    OSError(#[from] OSError),
+}
+
+/// Path of the answers file. It's placed in `.dotmake` directory of the
+/// dotfiles directory, that is ignored by git
+fn answers_path() -> PathBuf {
+    cli::options()
+        .dotfiles_dir()
+        .join(".dotmake")
+        .join("answers.json")
+}
+
+/// Load saved answers. If there is no answers file yet, no answers are
+/// returned
+fn load_answers() -> Result<Map<String, Value>, PromptError> {
+    let path = answers_path();
+    if !path.exists() {
+        return Ok(Map::new());
+    }
+    serde_json::from_str(&os::read_to_string(&path)?)
+        .map_err(|err| PromptError::InvalidAnswers { path, err })
+}
+
+/// Save an answer to the answers file, creating the file along with a
+/// `.gitignore` beside it if they don't exist. The file is only readable by
+/// its owner, since it may contain passwords
+fn save_answer(var: &str, answer: &Value) -> Result<(), PromptError> {
+    let path = answers_path();
+    let gitignore = path.with_file_name(".gitignore");
+    if !gitignore.exists() {
+        os::write_file(&gitignore, "*\n")?;
+    }
+    let mut answers = load_answers()?;
+    answers.insert(var.to_string(), answer.clone());
+    let json = serde_json::to_string_pretty(&answers).unwrap();
+    Ok(os::write_private_file(&path, json + "\n")?)
+}
+
+impl Prompt {
+    /// Check if a value can be an answer to the prompt
+    fn is_valid(&self, answer: &Value) -> bool {
+        match (self.kind, answer) {
+            (PromptKind::Confirm, Value::Bool(_)) => true,
+            (PromptKind::Select, Value::String(s)) => self.choices.contains(s),
+            (PromptKind::Text, Value::String(_)) => true,
+            (PromptKind::Password, Value::String(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Ask the user for an answer. With `--noconfirm` the default answer is
+    /// returned instead
+    fn ask(&self) -> Result<Value, PromptError> {
+        if let Some(default) = &self.default {
+            if !self.is_valid(default) {
+                return Err(PromptError::InvalidDefault(self.var.clone()));
+            }
+        }
+        if cli::options().noconfirm() {
+            return self
+                .default
+                .clone()
+                .ok_or_else(|| PromptError::NoAnswer(self.var.clone()));
+        }
+
+        let answer = match self.kind {
+            PromptKind::Text => {
+                let default = self.default.as_ref().and_then(Value::as_str);
+                Value::String(io::input(&self.message, default))
+            }
+            PromptKind::Password => Value::String(io::password(&self.message)),
+            PromptKind::Select => {
+                if self.choices.is_empty() {
+                    return Err(PromptError::NoChoices(self.var.clone()));
+                }
+                let items: Vec<_> = self.choices.iter().map(String::as_str).collect();
+                let default = self.default.as_ref().and_then(Value::as_str);
+                let default = items.iter().position(|&item| Some(item) == default);
+                let choice = io::select(&self.message, &items, default.unwrap_or(0));
+                Value::String(self.choices[choice].clone())
+            }
+            PromptKind::Confirm => {
+                let default = self.default.as_ref().and_then(Value::as_bool);
+                Value::Bool(io::confirm(&self.message, default.unwrap_or(false)))
+            }
+        };
+        Ok(answer)
+    }
+}
+
+impl Action for Prompt {
+    fn perform(&self, ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        if ctx.conf.answers.borrow().contains_key(&self.var) {
+            return Ok(());
+        }
+
+        let saved = match self.save {
+            true => load_answers()?.remove(&self.var),
+            false => None,
+        };
+        let answer = match saved {
+            Some(answer) if self.is_valid(&answer) => {
+                print_info!("Using the saved answer to `{}`", self.var);
+                answer
+            }
+            _ => {
+                let answer = self.ask()?;
+                if self.save && !cli::options().dry_run() {
+                    save_answer(&self.var, &answer)?;
+                }
+                answer
+            }
+        };
+        if let PromptKind::Password = self.kind {
+            ctx.conf.passwords.borrow_mut().insert(self.var.clone());
+        }
+        ctx.conf.answers.borrow_mut().insert(self.var.clone(), answer);
+        Ok(())
+    }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        Some(format!("{}: {}", self.var, self.message))
+    }
+}
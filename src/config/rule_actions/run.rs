@@ -0,0 +1,121 @@
+//! An action that executes script files from the dotfiles directory
+
+use super::{Action, ActionContext};
+use crate::cli;
+use crate::config::deserializers::ShortOrFull;
+use crate::os::{self, OSError};
+use crate::types::UserPath;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::process::Command;
+use tempdir::TempDir;
+
+#[derive(Debug, Deserialize)]
+#[serde(from = "RunEnum")]
+pub struct Run {
+    file: UserPath,
+    args: Vec<String>,
+    env: HashMap<String, String>,
+    in_temp: bool,
+}
+
+/// A helper for deserializing `Run` either from a path or from a full
+/// description
+type RunEnum = ShortOrFull<UserPath, FullRun>;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FullRun {
+    file: UserPath,
+
+    #[serde(default)]
+    args: Vec<String>,
+
+    #[serde(default)]
+    env: HashMap<String, String>,
+
+    /// Run the script in a temporary directory instead of the dotfiles one
+    #[serde(default)]
+    in_temp: bool,
+}
+
+impl From<RunEnum> for Run {
+    fn from(run: RunEnum) -> Self {
+        match run {
+            ShortOrFull::Short(file) => Run {
+                file,
+                args: Vec::new(),
+                env: HashMap::new(),
+                in_temp: false,
+            },
+            ShortOrFull::Full(full) => Run {
+                file: full.file,
+                args: full.args,
+                env: full.env,
+                in_temp: full.in_temp,
+            },
+        }
+    }
+}
+
+/// Get the interpreter along with it's arguments from a shebang line of a
+/// given file, if it has one
+fn read_shebang(file: &Path) -> Result<Option<Vec<String>>, OSError> {
+    let first_line = match os::read_file(file)?.next() {
+        Some(line) => line?,
+        None => return Ok(None),
+    };
+    if !first_line.starts_with("#!") {
+        return Ok(None);
+    }
+    Ok(Some(
+        first_line[2..]
+            .split_whitespace()
+            .map(str::to_string)
+            .collect(),
+    ))
+}
+
+impl Run {
+    /// Create a command executing the script in a given directory
+    fn command(&self, file: &Path, ctx: &ActionContext, dir: &Path) -> Result<Command, OSError> {
+        let interpreter = match read_shebang(file)? {
+            Some(shebang) if !shebang.is_empty() => shebang,
+            _ => vec![ctx.shell.to_string()],
+        };
+        let mut cmd = Command::new(&interpreter[0]);
+        cmd.args(&interpreter[1..])
+            .arg(file)
+            .args(&self.args)
+            .envs(ctx.env()?)
+            .envs(&self.env)
+            .current_dir(dir);
+        Ok(cmd)
+    }
+}
+
+impl Action for Run {
+    fn perform(&self, ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        let dotfiles_dir = cli::options().dotfiles_dir();
+        let file = os::canonicalize(dotfiles_dir.join(&*self.file))?;
+        if cli::options().dry_run() {
+            print_info!("Would run `{}`", self.preview(ctx).unwrap_or_default());
+            return Ok(());
+        }
+        if self.in_temp {
+            let temp_dir = TempDir::new("dotmake")?;
+            os::run_command(&mut self.command(&file, ctx, temp_dir.path())?)?;
+            temp_dir.close()?;
+        } else {
+            os::run_command(&mut self.command(&file, ctx, dotfiles_dir)?)?;
+        }
+        Ok(())
+    }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        let mut cmd = vec![self.file.display().to_string()];
+        cmd.extend(self.args.iter().cloned());
+        Some(cmd.join(" "))
+    }
+}
@@ -1,39 +1,146 @@
 //! Actions that perform given shell scripts
 
-use super::{Action, RuleActionsConf};
+use super::{Action, ActionContext};
 use crate::cli;
-use crate::config::deserializers::List;
-use crate::os::run_shell_script;
+use crate::config::deserializers::{List, ShortOrFull};
+use crate::os;
+use std::collections::HashMap;
 use std::error::Error;
+use std::path::Path;
+use std::time::Duration;
 use tempdir::TempDir;
 
+/// A script along with an optional interpreter to run it with
+#[derive(Debug, Deserialize)]
+#[serde(from = "ScriptEnum")]
+struct Script {
+    lines: List<String>,
+    interpreter: Option<String>,
+    args: Vec<String>,
+    env: HashMap<String, String>,
+    timeout: Option<u64>,
+    retries: u32,
+}
+
+/// A helper for deserializing `Script` either from a list of lines or from a
+/// full description
+type ScriptEnum = ShortOrFull<List<String>, FullScript>;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FullScript {
+    script: List<String>,
+
+    /// An interpreter to use instead of the default shell
+    #[serde(default)]
+    interpreter: Option<String>,
+
+    /// Arguments passed to the interpreter
+    #[serde(default)]
+    args: Vec<String>,
+
+    #[serde(default)]
+    env: HashMap<String, String>,
+
+    /// Timeout in seconds
+    #[serde(default)]
+    timeout: Option<u64>,
+
+    /// Number of times the script is run again if it fails
+    #[serde(default)]
+    retries: u32,
+}
+
+impl From<ScriptEnum> for Script {
+    fn from(script: ScriptEnum) -> Self {
+        match script {
+            ShortOrFull::Short(lines) => Script {
+                lines,
+                interpreter: None,
+                args: Vec::new(),
+                env: HashMap::new(),
+                timeout: None,
+                retries: 0,
+            },
+            ShortOrFull::Full(full) => Script {
+                lines: full.script,
+                interpreter: full.interpreter,
+                args: full.args,
+                env: full.env,
+                timeout: full.timeout,
+                retries: full.retries,
+            },
+        }
+    }
+}
+
+impl Script {
+    /// Run the script in a given directory. Rule's shell is used, unless an
+    /// interpreter is specified. With `--dry-run` the script is only printed
+    fn run(&self, ctx: &ActionContext, dir: &Path) -> Result<(), os::OSError> {
+        if cli::options().dry_run() {
+            match self.preview() {
+                script if script.contains('\n') => print_info!("Would run:\n{}", script),
+                script => print_info!("Would run `{}`", script),
+            }
+            return Ok(());
+        }
+        let interpreter = self.interpreter.as_deref().unwrap_or(ctx.shell);
+        let mut env = ctx.env()?;
+        env.extend(self.env.clone());
+        os::Script::new(interpreter, &self.lines.join("\n"))
+            .args(&self.args)
+            .envs(&env)
+            .piped(ctx.conf.pipe_scripts)
+            .timeout(self.timeout.map(Duration::from_secs))
+            .retries(self.retries)
+            .run(dir)
+    }
+
+    fn preview(&self) -> String {
+        let script = self.lines.join("\n");
+        match &self.interpreter {
+            Some(interpreter) => {
+                let mut cmd = vec![interpreter.clone()];
+                cmd.extend(self.args.iter().cloned());
+                format!("Interpreter: {}\n{}", cmd.join(" "), script)
+            }
+            None => script,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(transparent)]
 pub struct ShellScript {
-    script: List<String>,
+    script: Script,
 }
 
 impl Action for ShellScript {
-    fn perform(&self, conf: &RuleActionsConf) -> Result<(), Box<dyn Error>> {
-        Ok(run_shell_script(
-            &conf.shell,
-            cli::options().dotfiles_dir(),
-            &self.script.join("\n"),
-        )?)
+    fn perform(&self, ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        Ok(self.script.run(ctx, cli::options().dotfiles_dir())?)
+    }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        Some(self.script.preview())
     }
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(transparent)]
 pub struct TempDirShellScript {
-    script: List<String>,
+    script: Script,
 }
 
 impl Action for TempDirShellScript {
-    fn perform(&self, conf: &RuleActionsConf) -> Result<(), Box<dyn Error>> {
+    fn perform(&self, ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
         let temp_dir = TempDir::new("dotmake")?;
-        run_shell_script(&conf.shell, temp_dir.path(), &self.script.join("\n"))?;
+        self.script.run(ctx, temp_dir.path())?;
         temp_dir.close()?;
         Ok(())
     }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        Some(self.script.preview())
+    }
 }
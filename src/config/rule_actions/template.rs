@@ -0,0 +1,104 @@
+//! An action that renders templates from the dotfiles directory
+
+use super::{preview_files, prepare_dest, Action, ActionContext, DestState, FilesMap};
+use super::RuleActionsConf;
+use crate::cli;
+use crate::os::{self, OSError};
+use handlebars::Handlebars;
+use serde_json::{json, Value};
+use std::env::set_current_dir;
+use std::error::Error;
+use std::fs;
+
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+pub struct Template {
+    templates: FilesMap,
+}
+
+impl Template {
+    /// Collect variables available in templates. Those are user-defined
+    /// variables from `vars` field of the configuration, answers to prompts
+    /// along with the `host` object, that contains facts about the current
+    /// system
+    fn data(conf: &RuleActionsConf) -> Result<Value, OSError> {
+        let mut data: serde_json::Map<_, _> = conf.vars.clone().into_iter().collect();
+        data.extend(conf.answers.borrow().clone());
+        data.insert(
+            "host".to_string(),
+            json!({
+                "distro": cli::options().distro_id()?,
+                "hostname": os::hostname()?,
+                "user": os::username()?,
+            }),
+        );
+        Ok(Value::Object(data))
+    }
+
+    /// Create a template registry. Dotfiles aren't HTML, so values are
+    /// rendered without escaping
+    fn handlebars() -> Handlebars<'static> {
+        let mut handlebars = Handlebars::new();
+        handlebars.set_strict_mode(true);
+        handlebars.register_escape_fn(handlebars::no_escape);
+        handlebars
+    }
+}
+
+impl Action for Template {
+    fn perform(&self, ctx: &ActionContext) -> Result<(), Box<dyn Error>> {
+        set_current_dir(cli::options().dotfiles_dir())?;
+        let handlebars = Self::handlebars();
+        let data = Self::data(ctx.conf)?;
+
+        for (source, dests) in &self.templates {
+            let template = os::read_to_string(source)?;
+            let rendered = handlebars
+                .render_template(&template, &data)
+                .map_err(|err| format!("Failed to render `{}`: {}", source.display(), err))?;
+
+            for dest in dests.iter() {
+                let up_to_date = || Ok(fs::read(dest.as_path())? == rendered.as_bytes());
+                if let DestState::UpToDate = prepare_dest(dest, ctx.conf, up_to_date)? {
+                    continue;
+                }
+                if cli::options().dry_run() {
+                    print_info!(
+                        "Would render `{}` to `{}`",
+                        source.display(),
+                        dest.display()
+                    );
+                    continue;
+                }
+                os::write_file(dest, &rendered)?;
+                fs::set_permissions(dest.as_path(), source.metadata()?.permissions())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn preview(&self, _ctx: &ActionContext) -> Option<String> {
+        Some(preview_files(&self.templates, false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Template;
+    use serde_json::json;
+
+    #[test]
+    fn values_are_not_escaped() {
+        let data = json!({ "email": "a&b <x@y.z>", "quote": "it's \"q\"" });
+        let rendered = Template::handlebars()
+            .render_template("email={{email}} quote={{quote}}", &data)
+            .unwrap();
+        assert_eq!(rendered, "email=a&b <x@y.z> quote=it's \"q\"");
+    }
+
+    #[test]
+    fn undefined_variables_are_errors() {
+        let rendered = Template::handlebars().render_template("{{missing}}", &json!({}));
+        assert!(rendered.is_err());
+    }
+}
@@ -2,7 +2,7 @@
 
 use crate::cli;
 use colored::*;
-use dialoguer::{theme::ColorfulTheme, Confirm};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Password, Select};
 use std::str;
 
 /// Supported massage types
@@ -100,3 +100,41 @@ macro_rules! confirm {
         $crate::io::confirm(&format!($($format_arg),*), $default)
     };
 }
+
+/// Selection prompt rendered at stderr. Returns index of the selected item
+///
+/// If noconfirm option is set by the user, `default` is returned without of any
+/// prompt being displayed
+pub fn select(prompt: &str, items: &[&str], default: usize) -> usize {
+    if cli::options().noconfirm() {
+        default
+    } else {
+        Select::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .items(items)
+            .default(default)
+            .interact()
+            .unwrap()
+    }
+}
+
+/// Text input prompt rendered at stderr. Unlike other prompts it ignores
+/// noconfirm option, so callers have to handle it themselves
+pub fn input(prompt: &str, default: Option<&str>) -> String {
+    let theme = ColorfulTheme::default();
+    let mut input = Input::with_theme(&theme);
+    input.with_prompt(prompt);
+    if let Some(default) = default {
+        input.default(default.to_string());
+    }
+    input.interact().unwrap()
+}
+
+/// Password prompt rendered at stderr. Unlike other prompts it ignores
+/// noconfirm option, so callers have to handle it themselves
+pub fn password(prompt: &str) -> String {
+    Password::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .interact()
+        .unwrap()
+}
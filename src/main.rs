@@ -14,6 +14,7 @@ extern crate serde;
 mod io;
 mod cli;
 mod config;
+mod state;
 mod types;
 mod os;
 
@@ -1,13 +1,19 @@
 //! Various functions providing more convenient way of interacting with OS and
 //! it's file system
 
+use crate::types::Mode;
 use once_cell::sync::OnceCell;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
-use std::fs::{self, File};
+use std::fs::{self, File, OpenOptions};
 use std::io::{self, prelude::*, BufReader};
-use std::os::unix;
+use std::os::unix::{self, fs::OpenOptionsExt, fs::PermissionsExt, process::CommandExt};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempdir::TempDir;
 use thiserror::Error;
 
 /// Various errors that can occure while interating with OS
@@ -32,6 +38,16 @@ pub enum OSError {
 
     #[error("Failed to obtain file name of `{0}`")]
     NoFileName(PathBuf),
+
+    #[error("Failed to determine the current user")]
+    UnknownUser,
+
+    #[error("Process timed out after {secs}s (attempt {attempt}/{attempts})")]
+    TimedOut {
+        secs: u64,
+        attempt: u32,
+        attempts: u32,
+    },
 }
 
 /// Get file name from the given path
@@ -100,6 +116,243 @@ pub fn move_file(source: impl AsRef<Path>, dest: impl AsRef<Path>) -> Result<(),
     })
 }
 
+/// Copy a file or a directory recursively, preserving permissions. Symlinks
+/// are copied as symlinks instead of being followed
+pub fn copy(source: impl AsRef<Path>, dest: impl AsRef<Path>) -> Result<(), OSError> {
+    let source = source.as_ref();
+    let dest = dest.as_ref();
+    let copy_err = |err| OSError::IO {
+        msg: format!(
+            "Failed to copy `{}` to `{}`",
+            source.display(),
+            dest.display()
+        ),
+        err,
+    };
+
+    ensure_parent_dir(dest)?;
+    let file_type = file_type(source)?;
+    if file_type.is_dir() {
+        ensure_dir_exists(dest)?;
+        for entry in fs::read_dir(source).map_err(copy_err)? {
+            let name = entry.map_err(copy_err)?.file_name();
+            copy(source.join(&name), dest.join(&name))?;
+        }
+        let permissions = source.metadata().map_err(copy_err)?.permissions();
+        fs::set_permissions(dest, permissions).map_err(copy_err)?;
+        return Ok(());
+    }
+
+    // An existing symlink would be followed by `fs::copy`, and can't be
+    // replaced by a new one
+    if matches!(dest.symlink_metadata(), Ok(metadata) if !metadata.is_dir()) {
+        remove_file(dest)?;
+    }
+    if file_type.is_symlink() {
+        symlink(fs::read_link(source).map_err(copy_err)?, dest)
+    } else {
+        fs::copy(source, dest).map_err(copy_err)?;
+        Ok(())
+    }
+}
+
+/// Get the type of a file without following symlinks
+fn file_type(file: &Path) -> Result<fs::FileType, OSError> {
+    let metadata = file.symlink_metadata().map_err(|err| OSError::IO {
+        msg: format!("Failed to obtain metadata for `{}`", file.display()),
+        err,
+    })?;
+    Ok(metadata.file_type())
+}
+
+/// Test, whether or not two files have the same contents. Directories are
+/// compared recursively, and symlinks are compared by their targets
+pub fn same_contents(first: impl AsRef<Path>, second: impl AsRef<Path>) -> Result<bool, OSError> {
+    let first = first.as_ref();
+    let second = second.as_ref();
+    let read_err = |path: &Path| {
+        let msg = format!("Failed to read `{}`", path.display());
+        move |err| OSError::IO { msg, err }
+    };
+
+    let (first_type, second_type) = (file_type(first)?, file_type(second)?);
+    if first_type.is_dir() && second_type.is_dir() {
+        let names = |dir: &Path| -> Result<HashSet<_>, OSError> {
+            let entries = fs::read_dir(dir).map_err(read_err(dir))?;
+            entries
+                .map(|entry| Ok(entry.map_err(read_err(dir))?.file_name()))
+                .collect()
+        };
+        let first_names = names(first)?;
+        if first_names != names(second)? {
+            return Ok(false);
+        }
+        for name in first_names {
+            if !same_contents(first.join(&name), second.join(&name))? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    } else if first_type.is_symlink() && second_type.is_symlink() {
+        let first = fs::read_link(first).map_err(read_err(first))?;
+        let second = fs::read_link(second).map_err(read_err(second))?;
+        Ok(first == second)
+    } else if first_type.is_file() && second_type.is_file() {
+        let first = fs::read(first).map_err(read_err(first))?;
+        let second = fs::read(second).map_err(read_err(second))?;
+        Ok(first == second)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Move a given file to the backup directory. If there is already a file with
+/// the same name in the backup directory, a number is appended to the name of
+/// the new one
+///
+/// Returns the path of the backup
+pub fn backup(file: impl AsRef<Path>, backup_dir: impl AsRef<Path>) -> Result<PathBuf, OSError> {
+    let file = file.as_ref();
+    let backup_dir = backup_dir.as_ref();
+    ensure_dir_exists(backup_dir)?;
+    let backup_file_path = get_backup_file_path(file, backup_dir)?;
+    move_file(file, &backup_file_path)?;
+    Ok(backup_file_path)
+}
+
+/// Copy a given file to the backup directory, leaving the original in place.
+/// Backup is named the same way as with [`backup`]
+///
+/// Returns the path of the backup
+pub fn backup_copy(
+    file: impl AsRef<Path>,
+    backup_dir: impl AsRef<Path>,
+) -> Result<PathBuf, OSError> {
+    let file = file.as_ref();
+    let backup_dir = backup_dir.as_ref();
+    ensure_dir_exists(backup_dir)?;
+    let backup_file_path = get_backup_file_path(file, backup_dir)?;
+    copy(file, &backup_file_path)?;
+    Ok(backup_file_path)
+}
+
+/// Get a path in the backup directory, that is not taken by any other file
+fn get_backup_file_path(file: &Path, backup_dir: &Path) -> Result<PathBuf, OSError> {
+    let fname = get_file_name(&file)?;
+    let mut backup_file_path = backup_dir.join(fname);
+
+    let mut i = 1u128;
+    while backup_file_path.exists() {
+        let mut next_fname = fname.to_os_string();
+        next_fname.push(&format!(" ({})", i));
+        backup_file_path.set_file_name(next_fname);
+        i += 1;
+    }
+
+    Ok(backup_file_path)
+}
+
+/// A wrapper aroung [`std::fs::write`] providing more informative error
+/// messages. Parent directory is created if it doesn't exist
+pub fn write_file(file: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<(), OSError> {
+    let file = file.as_ref();
+    ensure_parent_dir(file)?;
+    fs::write(file, contents).map_err(|err| OSError::IO {
+        msg: format!("Failed to write `{}`", file.display()),
+        err,
+    })
+}
+
+/// Write a file, that is only accessible by its owner. Parent directory is
+/// created if it doesn't exist
+pub fn write_private_file(
+    file: impl AsRef<Path>,
+    contents: impl AsRef<[u8]>,
+) -> Result<(), OSError> {
+    let file = file.as_ref();
+    ensure_parent_dir(file)?;
+    let write = || -> io::Result<()> {
+        let mut f = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(file)?;
+        f.set_permissions(fs::Permissions::from_mode(0o600))?;
+        f.write_all(contents.as_ref())
+    };
+    write().map_err(|err| OSError::IO {
+        msg: format!("Failed to write `{}`", file.display()),
+        err,
+    })
+}
+
+/// A wrapper aroung [`std::fs::read_to_string`] providing more informative
+/// error messages
+pub fn read_to_string(file: impl AsRef<Path>) -> Result<String, OSError> {
+    let file = file.as_ref();
+    fs::read_to_string(file).map_err(|err| OSError::IO {
+        msg: format!("Failed to read `{}`", file.display()),
+        err,
+    })
+}
+
+/// Get permission bits of a given file
+pub fn get_mode(file: impl AsRef<Path>) -> Result<Mode, OSError> {
+    Ok(Mode::from(metadata(file)?.permissions().mode()))
+}
+
+/// Set permission bits of a given file
+pub fn set_mode(file: impl AsRef<Path>, mode: Mode) -> Result<(), OSError> {
+    let file = file.as_ref();
+    let permissions = fs::Permissions::from_mode(mode.bits());
+    fs::set_permissions(file, permissions).map_err(|err| OSError::IO {
+        msg: format!("Failed to change mode of `{}`", file.display()),
+        err,
+    })
+}
+
+/// A wrapper aroung [`std::os::unix::fs::chown`] providing more informative
+/// error messages
+pub fn chown(file: impl AsRef<Path>, uid: Option<u32>, gid: Option<u32>) -> Result<(), OSError> {
+    let file = file.as_ref();
+    unix::fs::chown(file, uid, gid).map_err(|err| OSError::IO {
+        msg: format!("Failed to change owner of `{}`", file.display()),
+        err,
+    })
+}
+
+/// A wrapper aroung [`std::fs::metadata`] providing more informative error
+/// messages
+pub fn metadata(file: impl AsRef<Path>) -> Result<fs::Metadata, OSError> {
+    let file = file.as_ref();
+    fs::metadata(file).map_err(|err| OSError::IO {
+        msg: format!("Failed to obtain metadata for `{}`", file.display()),
+        err,
+    })
+}
+
+/// Compute SHA-256 checksum of a given file as a lowercase hex string
+pub fn sha256_file(file: impl AsRef<Path>) -> Result<String, OSError> {
+    let file = file.as_ref();
+    let mut hasher = Sha256::new();
+    io::copy(&mut open_file(file)?, &mut hasher).map_err(|err| OSError::IO {
+        msg: format!("Failed to read `{}`", file.display()),
+        err,
+    })?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// A wrapper aroung [`std::fs::canonicalize`] providing more informative
+/// error messages
+pub fn canonicalize(file: impl AsRef<Path>) -> Result<PathBuf, OSError> {
+    let file = file.as_ref();
+    fs::canonicalize(file).map_err(|err| OSError::IO {
+        msg: format!("Failed to resolve `{}`", file.display()),
+        err,
+    })
+}
+
 /// A wrapper aroung [`std::fs::remove_file`] providing more informative error
 /// messages
 pub fn remove_file(file: impl AsRef<Path>) -> Result<(), OSError> {
@@ -174,34 +427,31 @@ pub fn distro_id() -> Result<&'static str, OSError> {
     })?)
 }
 
-/// Run shell scrip in the given directory
+/// Read host name from `/proc/sys/kernel/hostname`
+pub fn hostname() -> Result<&'static str, OSError> {
+    static HOSTNAME: OnceCell<String> = OnceCell::new();
+    Ok(HOSTNAME.get_or_try_init(|| {
+        Ok(read_to_string("/proc/sys/kernel/hostname")?.trim().to_string())
+    })?)
+}
+
+/// Get name of the current user
+pub fn username() -> Result<String, OSError> {
+    users::get_current_username()
+        .and_then(|name| name.into_string().ok())
+        .ok_or(OSError::UnknownUser)
+}
+
+/// Run a given command and wait for it to finish
 ///
 /// # Errors
-/// Incomprehensive list of possible error cases:
-/// - `shell` is not a valid executable;
-/// - `dir` does not exist or is not a directory;
-/// - piping to shell process fails;
-/// - script fails during execution.
-///
-/// # Example
-/// ```
-/// run_shell_script("bash", "./", "echo hello").unwrap(); // prints "hello" to the screen
-/// run_shell_script("fish", "/etc", "echo (pwd)").unwrap(); // prints "/etc" to the screen
-/// ```
-pub fn run_shell_script(shell: &str, dir: impl AsRef<Path>, script: &str) -> Result<(), OSError> {
-    let shell_err = |err| OSError::IO {
-        msg: "Shell error".to_string(),
+/// Returns an error if the command fails to start or exits with non-zero
+/// status code
+pub fn run_command(cmd: &mut Command) -> Result<(), OSError> {
+    let exit_status = cmd.status().map_err(|err| OSError::IO {
+        msg: format!("Failed to run `{}`", cmd.get_program().to_string_lossy()),
         err,
-    };
-    let mut shell = Command::new(shell)
-        .current_dir(dir)
-        .stdin(Stdio::piped())
-        .spawn()
-        .map_err(shell_err)?;
-
-    write!(shell.stdin.as_mut().unwrap(), "{}", script).map_err(shell_err)?;
-
-    let exit_status = shell.wait().map_err(shell_err)?;
+    })?;
     if !exit_status.success() {
         Err(OSError::BadExitStatus {
             code: exit_status.code(),
@@ -209,3 +459,349 @@ pub fn run_shell_script(shell: &str, dir: impl AsRef<Path>, script: &str) -> Res
     }
     Ok(())
 }
+
+/// Run a given command quietly and check whether it succeeds
+///
+/// # Errors
+/// Returns an error if the command fails to start
+pub fn command_succeeds(cmd: &mut Command) -> Result<bool, OSError> {
+    let exit_status = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|err| OSError::IO {
+            msg: format!("Failed to run `{}`", cmd.get_program().to_string_lossy()),
+            err,
+        })?;
+    Ok(exit_status.success())
+}
+
+/// Run a given command quietly and capture it's standard output. Returns
+/// `None` if the command fails
+///
+/// # Errors
+/// Returns an error if the command fails to start
+pub fn quiet_output(cmd: &mut Command) -> Result<Option<String>, OSError> {
+    let output = cmd
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|err| OSError::IO {
+            msg: format!("Failed to run `{}`", cmd.get_program().to_string_lossy()),
+            err,
+        })?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+/// Run a given command and capture it's standard output
+///
+/// # Errors
+/// Returns an error if the command fails to start or exits with non-zero
+/// status code
+pub fn command_output(cmd: &mut Command) -> Result<String, OSError> {
+    Ok(String::from_utf8_lossy(&command_stdout(cmd)?).into_owned())
+}
+
+/// Run a given command and capture it's raw standard output
+///
+/// # Errors
+/// Returns an error if the command fails to start or exits with non-zero
+/// status code
+pub fn command_stdout(cmd: &mut Command) -> Result<Vec<u8>, OSError> {
+    let output = cmd
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|err| OSError::IO {
+            msg: format!("Failed to run `{}`", cmd.get_program().to_string_lossy()),
+            err,
+        })?;
+    if !output.status.success() {
+        Err(OSError::BadExitStatus {
+            code: output.status.code(),
+        })?;
+    }
+    Ok(output.stdout)
+}
+
+/// A script, that is run by an interpreter. By default the script is passed
+/// to the interpreter as a temporary file, so that the standard input is
+/// inherited from dotmake
+///
+/// # Example
+/// ```
+/// let args = vec!["-u".to_string()];
+/// Script::new("python3", "print('hello')").args(&args).run("./").unwrap();
+/// Script::new("bash", "echo hello").piped(true).run("./").unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Script<'a> {
+    interpreter: &'a str,
+    args: &'a [String],
+    body: &'a str,
+    piped: bool,
+    env: Option<&'a HashMap<String, String>>,
+    timeout: Option<Duration>,
+    retries: u32,
+}
+
+/// A delay before the first retry of a failed script. Every next delay is
+/// twice as long
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+impl<'a> Script<'a> {
+    /// Create a new script run by a given interpreter
+    pub fn new(interpreter: &'a str, body: &'a str) -> Self {
+        Script {
+            interpreter,
+            args: &[],
+            body,
+            piped: false,
+            env: None,
+            timeout: None,
+            retries: 0,
+        }
+    }
+
+    /// Set arguments passed to the interpreter
+    pub fn args(mut self, args: &'a [String]) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// Set environment variables exported to the script
+    pub fn envs(mut self, env: &'a HashMap<String, String>) -> Self {
+        self.env = Some(env);
+        self
+    }
+
+    /// Pipe the script to the interpreter's standard input instead of passing
+    /// it as a file
+    pub fn piped(mut self, piped: bool) -> Self {
+        self.piped = piped;
+        self
+    }
+
+    /// Kill the script's process group if it runs longer than a given time.
+    /// Such scripts are run in their own process group, which is given the
+    /// terminal until the script exits
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set how many times the script is run again if it fails
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Run the script in the given directory. A failed script is retried with
+    /// exponentially growing delays until there are no retries left
+    ///
+    /// # Errors
+    /// Incomprehensive list of possible error cases:
+    /// - interpreter is not a valid executable;
+    /// - `dir` does not exist or is not a directory;
+    /// - writing the script to a temporary file or piping it fails;
+    /// - script fails during execution or times out.
+    pub fn run(&self, dir: impl AsRef<Path>) -> Result<(), OSError> {
+        let attempts = self.retries + 1;
+        let mut delay = RETRY_DELAY;
+        for attempt in 1..attempts {
+            match self.run_once(dir.as_ref(), attempt, attempts) {
+                Ok(()) => return Ok(()),
+                Err(err) => {
+                    print_warn!("{}, retrying in {}s", err, delay.as_secs());
+                    thread::sleep(delay);
+                    delay *= 2;
+                }
+            }
+        }
+        self.run_once(dir.as_ref(), attempts, attempts)
+    }
+
+    /// Run the script once
+    fn run_once(&self, dir: &Path, attempt: u32, attempts: u32) -> Result<(), OSError> {
+        let shell_err = |err| OSError::IO {
+            msg: "Shell error".to_string(),
+            err,
+        };
+        let mut cmd = Command::new(self.interpreter);
+        cmd.args(self.args).current_dir(dir);
+        if let Some(env) = self.env {
+            cmd.envs(env);
+        }
+        if self.timeout.is_some() {
+            cmd.process_group(0);
+        }
+
+        let temp_dir = match self.piped {
+            true => None,
+            false => Some(TempDir::new("dotmake").map_err(shell_err)?),
+        };
+        let mut shell = match &temp_dir {
+            None => {
+                let mut shell = cmd.stdin(Stdio::piped()).spawn().map_err(shell_err)?;
+                let mut stdin = shell.stdin.take().unwrap();
+                write!(stdin, "{}", self.body).map_err(shell_err)?;
+                shell
+            }
+            Some(dir) => {
+                let file = dir.path().join("script");
+                write_file(&file, self.body)?;
+                cmd.arg(&file).spawn().map_err(shell_err)?
+            }
+        };
+
+        let terminal = match self.timeout {
+            Some(_) => Terminal::give_to(shell.id() as libc::pid_t),
+            None => None,
+        };
+        let exit_status = self.wait(&mut shell, attempt, attempts);
+        drop(terminal);
+        if let Some(dir) = temp_dir {
+            dir.close().map_err(shell_err)?;
+        }
+        let exit_status = exit_status?;
+        if !exit_status.success() {
+            Err(OSError::BadExitStatus {
+                code: exit_status.code(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Wait for the script's process to exit. If it times out, its process
+    /// group is killed
+    fn wait(&self, shell: &mut Child, attempt: u32, attempts: u32) -> Result<ExitStatus, OSError> {
+        let shell_err = |err| OSError::IO {
+            msg: "Shell error".to_string(),
+            err,
+        };
+        let timeout = match self.timeout {
+            Some(timeout) => timeout,
+            None => return shell.wait().map_err(shell_err),
+        };
+
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            if let Some(status) = shell.try_wait().map_err(shell_err)? {
+                return Ok(status);
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        // The process is the leader of its own group, so it's group id is the
+        // same as it's pid
+        unsafe {
+            libc::kill(-(shell.id() as libc::pid_t), libc::SIGKILL);
+        }
+        shell.wait().map_err(shell_err)?;
+        Err(OSError::TimedOut {
+            secs: timeout.as_secs(),
+            attempt,
+            attempts,
+        })
+    }
+}
+
+/// The controlling terminal given to another process group. It's taken back
+/// once dropped
+struct Terminal {
+    pgrp: libc::pid_t,
+}
+
+impl Terminal {
+    /// Make a process group the foreground one, so that it can read from the
+    /// terminal. Nothing is done, if dotmake doesn't own the terminal itself
+    fn give_to(pgrp: libc::pid_t) -> Option<Self> {
+        unsafe {
+            let own_pgrp = libc::getpgrp();
+            if libc::isatty(libc::STDIN_FILENO) == 0
+                || libc::tcgetpgrp(libc::STDIN_FILENO) != own_pgrp
+            {
+                return None;
+            }
+            set_foreground(pgrp);
+            // The group may have been stopped by reading from the terminal
+            // before it got it
+            libc::killpg(pgrp, libc::SIGCONT);
+            Some(Terminal { pgrp: own_pgrp })
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        unsafe { set_foreground(self.pgrp) }
+    }
+}
+
+/// Make a process group the foreground one. `SIGTTOU` is ignored meanwhile,
+/// since a background process is stopped by it otherwise
+unsafe fn set_foreground(pgrp: libc::pid_t) {
+    let handler = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+    libc::tcsetpgrp(libc::STDIN_FILENO, pgrp);
+    libc::signal(libc::SIGTTOU, handler);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{copy, same_contents};
+    use std::fs;
+    use std::os::unix::fs::symlink;
+    use std::path::Path;
+    use tempdir::TempDir;
+
+    /// Create a directory with a file and given symlinks in it
+    fn dir_with_links(dir: &Path, links: &[(&str, &str)]) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("file"), "contents").unwrap();
+        for (link, target) in links {
+            symlink(target, dir.join(link)).unwrap();
+        }
+    }
+
+    #[test]
+    fn symlinks_are_copied_as_is() {
+        let temp = TempDir::new("dotmake-test").unwrap();
+        let (source, dest) = (temp.path().join("source"), temp.path().join("dest"));
+        let links = [("link", "file"), ("dangling", "missing"), ("loop", ".")];
+        dir_with_links(&source, &links);
+
+        copy(&source, &dest).unwrap();
+        for (link, target) in &links {
+            assert_eq!(fs::read_link(dest.join(link)).unwrap(), Path::new(target));
+        }
+        assert_eq!(fs::read_to_string(dest.join("file")).unwrap(), "contents");
+        assert!(same_contents(&source, &dest).unwrap());
+    }
+
+    #[test]
+    fn existing_symlinks_are_replaced() {
+        let temp = TempDir::new("dotmake-test").unwrap();
+        let (source, dest) = (temp.path().join("source"), temp.path().join("dest"));
+        dir_with_links(&source, &[("link", "file")]);
+        dir_with_links(&dest, &[("link", "other")]);
+        fs::remove_file(dest.join("file")).unwrap();
+        symlink("other", dest.join("file")).unwrap();
+
+        assert!(!same_contents(&source, &dest).unwrap());
+        copy(&source, &dest).unwrap();
+        assert!(same_contents(&source, &dest).unwrap());
+        assert!(!dest.join("other").exists());
+    }
+
+    #[test]
+    fn symlinks_differ_from_their_targets() {
+        let temp = TempDir::new("dotmake-test").unwrap();
+        dir_with_links(temp.path(), &[("link", "file")]);
+        let (file, link) = (temp.path().join("file"), temp.path().join("link"));
+        assert!(!same_contents(&file, &link).unwrap());
+        assert!(same_contents(&link, &link).unwrap());
+    }
+}
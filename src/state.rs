@@ -0,0 +1,111 @@
+//! Dotmake's state, that is kept between runs, e.g. the list of installed rules
+//! or lines added to files
+
+use crate::os::{self, OSError};
+use crate::types::Identifier;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Errors that can occure while loading or saving the state
+#[derive(Debug, Error)]
+pub enum StateError {
+    #[error(transparent)]
+    OSError(#[from] OSError),
+
+    #[error("Invalid state file `{path}`: {err}")]
+    InvalidState {
+        path: PathBuf,
+        #[source]
+        err: serde_json::Error,
+    },
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct State {
+    #[serde(default)]
+    installed_rules: BTreeSet<Identifier>,
+
+    /// Files edited by `line_in_file` and `block_in_file` actions. Lines added
+    /// by `line_in_file` actions are mapped to the lines they've replaced, if
+    /// any
+    #[serde(default)]
+    edited_files: BTreeMap<PathBuf, BTreeMap<String, Option<String>>>,
+}
+
+impl State {
+    /// Path of the state file. It's placed in `$XDG_STATE_HOME/dotmake` or in
+    /// `~/.local/state/dotmake` if the variable isn't set
+    fn path() -> PathBuf {
+        let state_home = match env::var_os("XDG_STATE_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(shellexpand::tilde("~/.local/state").to_string()),
+        };
+        state_home.join("dotmake").join("state.json")
+    }
+
+    /// Load the state. If there is no state file yet, an empty state is
+    /// returned
+    pub fn load() -> Result<Self, StateError> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(State::default());
+        }
+        serde_json::from_str(&os::read_to_string(&path)?)
+            .map_err(|err| StateError::InvalidState { path, err })
+    }
+
+    /// Save the state, creating the state file if it doesn't exist
+    pub fn save(&self) -> Result<(), StateError> {
+        let json = serde_json::to_string_pretty(self).unwrap();
+        Ok(os::write_file(Self::path(), json + "\n")?)
+    }
+
+    /// Load the state, change it and save it right away, so that changes
+    /// saved by others in the meantime aren't lost
+    pub fn update(change: impl FnOnce(&mut Self)) -> Result<(), StateError> {
+        let mut state = Self::load()?;
+        change(&mut state);
+        state.save()
+    }
+
+    /// Get rules that are installed by `dotmake install`
+    pub fn installed_rules(&self) -> impl Iterator<Item = &Identifier> {
+        self.installed_rules.iter()
+    }
+
+    /// Mark a rule as installed
+    pub fn add_installed(&mut self, rule: Identifier) {
+        self.installed_rules.insert(rule);
+    }
+
+    /// Mark a rule as not installed
+    pub fn remove_installed(&mut self, rule: &Identifier) {
+        self.installed_rules.remove(rule);
+    }
+
+    /// Check if a file has already been edited by dotmake
+    pub fn is_edited(&self, file: &Path) -> bool {
+        self.edited_files.contains_key(file)
+    }
+
+    /// Mark a file as edited by dotmake
+    pub fn add_edited(&mut self, file: &Path) {
+        self.edited_files.entry(file.to_owned()).or_default();
+    }
+
+    /// Remember a line added to a file along with the line it has replaced
+    pub fn add_line(&mut self, file: &Path, line: String, replaced: Option<String>) {
+        self.edited_files
+            .entry(file.to_owned())
+            .or_default()
+            .insert(line, replaced);
+    }
+
+    /// Forget a line added to a file. Returns the line it has replaced, or
+    /// `None` if the line wasn't added by dotmake
+    pub fn take_line(&mut self, file: &Path, line: &str) -> Option<Option<String>> {
+        self.edited_files.get_mut(file)?.remove(line)
+    }
+}
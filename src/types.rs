@@ -9,7 +9,8 @@ use std::str;
 use std::str::FromStr;
 
 /// A structure representing a string containing no whitespace
-#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Serialize)]
+#[serde(transparent)]
 pub struct Identifier(String);
 
 impl Identifier {
@@ -134,3 +135,93 @@ impl AsRef<Path> for UserPath {
         &self.path
     }
 }
+
+/// Unix file mode, that is deserialized from an octal number, e.g. `"0700"` or
+/// `700`
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Mode(u32);
+
+impl Mode {
+    /// Get mode bits
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for Mode {
+    fn from(bits: u32) -> Self {
+        Mode(bits & 0o7777)
+    }
+}
+
+impl<'de> Deserialize<'de> for Mode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawMode {
+            Number(u64),
+            String(String),
+        }
+
+        let s = match RawMode::deserialize(deserializer)? {
+            RawMode::Number(n) => n.to_string(),
+            RawMode::String(s) => s,
+        };
+        s.parse().map_err(|_| {
+            Error::invalid_value(de::Unexpected::Str(&s), &"an octal file mode, e.g. 0755")
+        })
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.trim_start_matches("0o");
+        match u32::from_str_radix(digits, 8) {
+            Ok(bits) if bits <= 0o7777 => Ok(Mode(bits)),
+            _ => Err(format!("invalid file mode: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04o}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mode;
+
+    #[test]
+    fn octal_modes() {
+        assert_eq!("0755".parse(), Ok(Mode(0o755)));
+        assert_eq!("600".parse(), Ok(Mode(0o600)));
+        assert_eq!("0o700".parse(), Ok(Mode(0o700)));
+        assert_eq!("4755".parse(), Ok(Mode(0o4755)));
+    }
+
+    #[test]
+    fn invalid_modes() {
+        for s in &["", "0o", "0855", "rwx", "-644", "17777"] {
+            assert!(s.parse::<Mode>().is_err(), "`{}` is a valid mode", s);
+        }
+    }
+
+    #[test]
+    fn yaml_modes() {
+        let modes: Vec<Mode> = serde_yaml::from_str("[0600, 755, '0700']").unwrap();
+        assert_eq!(modes, vec![Mode(0o600), Mode(0o755), Mode(0o700)]);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Mode(0o644).to_string(), "0644");
+        assert_eq!(Mode(0o4755).to_string(), "4755");
+    }
+}